@@ -0,0 +1,96 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! JSON output in the layout libsmartcols uses: 3 spaces of indentation, and objects
+//! within an array chained as `},{`.
+
+use serde_json::Value;
+
+const INDENT: &str = "   ";
+
+pub fn to_string(value: &Value) -> String {
+    let mut out = String::new();
+    write_value(&mut out, value, 0);
+    out
+}
+
+fn write_indent(out: &mut String, depth: usize) {
+    for _ in 0..depth {
+        out.push_str(INDENT);
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            out.push_str("{\n");
+            for (i, (key, value)) in map.iter().enumerate() {
+                write_indent(out, depth + 1);
+                out.push_str(&Value::from(key.as_str()).to_string());
+                out.push_str(": ");
+                write_value(out, value, depth + 1);
+                if i + 1 < map.len() {
+                    out.push(',');
+                }
+                out.push('\n');
+            }
+            write_indent(out, depth);
+            out.push('}');
+        }
+        Value::Array(items) if !items.is_empty() => {
+            out.push_str("[\n");
+            write_indent(out, depth + 1);
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    if item.is_object() && items[i - 1].is_object() {
+                        out.push(',');
+                    } else {
+                        out.push_str(",\n");
+                        write_indent(out, depth + 1);
+                    }
+                }
+                write_value(out, item, depth + 1);
+            }
+            out.push('\n');
+            write_indent(out, depth);
+            out.push(']');
+        }
+        // Scalars, as well as empty objects and arrays, fit on one line
+        _ => out.push_str(&value.to_string()),
+    }
+}
+
+#[test]
+fn test_to_string() {
+    let value = serde_json::json!({
+        "memory": [
+            {"range": "0x0-0xf", "removable": true},
+            {"range": "0x10-0x1f", "removable": false, "children": []},
+        ],
+        "nodes": [0, "a\"b"],
+        "summary": {},
+    });
+
+    assert_eq!(
+        to_string(&value),
+        r#"{
+   "memory": [
+      {
+         "range": "0x0-0xf",
+         "removable": true
+      },{
+         "range": "0x10-0x1f",
+         "removable": false,
+         "children": []
+      }
+   ],
+   "nodes": [
+      0,
+      "a\"b"
+   ],
+   "summary": {}
+}"#
+    );
+}
@@ -0,0 +1,676 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Table formatting shared by the listing utilities, modelled after util-linux's libsmartcols.
+//!
+//! A [`Table`] is made of [`Column`] definitions and [`Line`]s of cells, which can be nested to
+//! form a tree. The same table can be printed as aligned columns, raw, `KEY="value"` pairs or JSON.
+
+pub mod json;
+
+use serde_json::{Map, Number, Value};
+use std::io::IsTerminal;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum OutputFormat {
+    /// Columns aligned with spaces
+    #[default]
+    Table,
+    /// Cells separated by a single space, with blanks escaped
+    Raw,
+    /// `KEY="value"` pairs, one line per row
+    Pairs,
+    /// An object holding an array of rows, keyed by the table name
+    Json,
+}
+
+/// How a cell is represented in JSON output. Empty cells are always `null`.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum JsonType {
+    #[default]
+    String,
+    /// The cell is output unquoted if it parses as a number, and as a string otherwise
+    Number,
+    /// `0`, `n`, `no` and `false` are false, anything else is true
+    Boolean,
+}
+
+/// How values wider than their column are shown when the table doesn't fit in the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Overflow {
+    /// The column is never shrunk below its content
+    #[default]
+    None,
+    /// Values are cut to the column width
+    Truncate,
+    /// Values are split over several lines, preferably on `/`
+    Wrap,
+}
+
+#[derive(Clone, Debug)]
+pub struct Column {
+    name: String,
+    right: bool,
+    width_hint: usize,
+    overflow: Overflow,
+    json_type: JsonType,
+    tree: bool,
+    placeholder: String,
+}
+
+impl Column {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            right: false,
+            width_hint: 0,
+            overflow: Overflow::None,
+            json_type: JsonType::String,
+            tree: false,
+            placeholder: String::new(),
+        }
+    }
+
+    pub fn right(mut self, right: bool) -> Self {
+        self.right = right;
+        self
+    }
+
+    /// Minimum width of the column, which it is also never shrunk below.
+    pub fn width_hint(mut self, width_hint: usize) -> Self {
+        self.width_hint = width_hint;
+        self
+    }
+
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    pub fn json_type(mut self, json_type: JsonType) -> Self {
+        self.json_type = json_type;
+        self
+    }
+
+    /// Indents the cells of this column to show the nesting of lines.
+    pub fn tree(mut self, tree: bool) -> Self {
+        self.tree = tree;
+        self
+    }
+
+    /// Text shown for empty cells, except in JSON where they are `null`.
+    pub fn placeholder(mut self, placeholder: &str) -> Self {
+        self.placeholder = placeholder.to_string();
+        self
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn min_width(&self) -> usize {
+        self.width_hint.max(self.name.chars().count())
+    }
+
+    fn to_json(&self, cell: Option<&str>) -> Value {
+        let Some(data) = cell.filter(|data| !data.is_empty()) else {
+            return Value::Null;
+        };
+        match self.json_type {
+            JsonType::String => data.into(),
+            JsonType::Number => data
+                .parse::<Number>()
+                .map(Value::Number)
+                .unwrap_or_else(|_| data.into()),
+            JsonType::Boolean => {
+                (!matches!(data.to_lowercase().as_str(), "0" | "n" | "no" | "false")).into()
+            }
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Line {
+    cells: Vec<Option<String>>,
+    children: Vec<Line>,
+}
+
+impl Line {
+    /// A line with one cell per column, in the same order. `None` marks an empty cell.
+    pub fn new(cells: impl IntoIterator<Item = Option<String>>) -> Self {
+        Self {
+            cells: cells.into_iter().collect(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn add_child(&mut self, child: Line) {
+        self.children.push(child);
+    }
+
+    fn get(&self, column: usize) -> Option<&str> {
+        self.cells.get(column).and_then(|cell| cell.as_deref())
+    }
+}
+
+impl From<Vec<String>> for Line {
+    fn from(cells: Vec<String>) -> Self {
+        Self::new(cells.into_iter().map(Some))
+    }
+}
+
+/// The strings drawn in front of the cells of a tree column.
+#[derive(Clone, Debug)]
+pub struct TreeSymbols {
+    /// In front of a line followed by siblings
+    pub branch: String,
+    /// Below a line followed by siblings, in front of its descendants
+    pub vertical: String,
+    /// In front of the last line among its siblings
+    pub right: String,
+}
+
+impl Default for TreeSymbols {
+    fn default() -> Self {
+        Self {
+            branch: "├─".to_string(),
+            vertical: "│ ".to_string(),
+            right: "└─".to_string(),
+        }
+    }
+}
+
+pub struct Table {
+    name: String,
+    columns: Vec<Column>,
+    lines: Vec<Line>,
+    format: OutputFormat,
+    noheadings: bool,
+    width: Option<usize>,
+    symbols: TreeSymbols,
+}
+
+/// Width of the terminal the output has to fit in, if stdout is a terminal.
+/// Like util-linux, `$COLUMNS` takes precedence over the size reported by the terminal.
+pub fn terminal_width() -> Option<usize> {
+    if !std::io::stdout().is_terminal() {
+        return None;
+    }
+    let columns = std::env::var("COLUMNS")
+        .ok()
+        .and_then(|columns| columns.parse().ok());
+    Some(columns.unwrap_or_else(textwrap::termwidth))
+}
+
+impl Table {
+    /// A table whose rows are listed under `name` in JSON output.
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            columns: Vec::new(),
+            lines: Vec::new(),
+            format: OutputFormat::Table,
+            noheadings: false,
+            width: terminal_width(),
+            symbols: TreeSymbols::default(),
+        }
+    }
+
+    pub fn add_column(&mut self, column: Column) {
+        self.columns.push(column);
+    }
+
+    pub fn add_line(&mut self, line: Line) {
+        self.lines.push(line);
+    }
+
+    pub fn set_format(&mut self, format: OutputFormat) {
+        self.format = format;
+    }
+
+    pub fn set_noheadings(&mut self, noheadings: bool) {
+        self.noheadings = noheadings;
+    }
+
+    /// Overrides the terminal width, which is detected when the table is created.
+    pub fn set_width(&mut self, width: Option<usize>) {
+        self.width = width;
+    }
+
+    pub fn set_tree_symbols(&mut self, symbols: TreeSymbols) {
+        self.symbols = symbols;
+    }
+
+    pub fn print(&self) {
+        print!("{}", self.render());
+    }
+
+    pub fn render(&self) -> String {
+        match self.format {
+            OutputFormat::Table => self.render_table(),
+            OutputFormat::Raw => self.render_raw(),
+            OutputFormat::Pairs => self.render_pairs(),
+            OutputFormat::Json => json::to_string(&self.to_json()) + "\n",
+        }
+    }
+
+    /// The table as a JSON object, for callers that add their own fields before printing it with
+    /// [`json::to_string`]. Column names are used as keys in lowercase, and children of a line are
+    /// listed under `children`.
+    pub fn to_json(&self) -> Value {
+        fn line_to_json(columns: &[Column], line: &Line) -> Value {
+            let mut record: Map<String, Value> = columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| (column.name.to_lowercase(), column.to_json(line.get(i))))
+                .collect();
+            if !line.children.is_empty() {
+                let children = line
+                    .children
+                    .iter()
+                    .map(|child| line_to_json(columns, child))
+                    .collect();
+                record.insert("children".to_string(), Value::Array(children));
+            }
+            Value::Object(record)
+        }
+
+        let records = self
+            .lines
+            .iter()
+            .map(|line| line_to_json(&self.columns, line))
+            .collect();
+        let mut table = Map::new();
+        table.insert(self.name.clone(), Value::Array(records));
+        Value::Object(table)
+    }
+
+    fn cell_text<'a>(&'a self, line: &'a Line, column: usize) -> &'a str {
+        line.get(column)
+            .filter(|data| !data.is_empty())
+            .unwrap_or(&self.columns[column].placeholder)
+    }
+
+    /// All lines in display order, children right after their parent.
+    fn flatten(&self) -> Vec<&Line> {
+        fn walk<'a>(out: &mut Vec<&'a Line>, lines: &'a [Line]) {
+            for line in lines {
+                out.push(line);
+                walk(out, &line.children);
+            }
+        }
+        let mut out = Vec::new();
+        walk(&mut out, &self.lines);
+        out
+    }
+
+    fn render_raw(&self) -> String {
+        let mut out = String::new();
+        if !self.noheadings {
+            let names: Vec<_> = self.columns.iter().map(|c| escape_raw(&c.name)).collect();
+            out.push_str(&names.join(" "));
+            out.push('\n');
+        }
+        for line in self.flatten() {
+            let values: Vec<_> = (0..self.columns.len())
+                .map(|i| escape_raw(self.cell_text(line, i)))
+                .collect();
+            out.push_str(&values.join(" "));
+            out.push('\n');
+        }
+        out
+    }
+
+    fn render_pairs(&self) -> String {
+        let mut out = String::new();
+        for line in self.flatten() {
+            let pairs: Vec<_> = self
+                .columns
+                .iter()
+                .enumerate()
+                .map(|(i, column)| {
+                    format!(
+                        "{}=\"{}\"",
+                        column.name,
+                        escape_pair(self.cell_text(line, i))
+                    )
+                })
+                .collect();
+            out.push_str(&pairs.join(" "));
+            out.push('\n');
+        }
+        out
+    }
+
+    /// The cells of every line as they are printed, with the tree drawn in the tree column.
+    fn table_cells(&self) -> Vec<Vec<String>> {
+        fn walk(
+            table: &Table,
+            out: &mut Vec<Vec<String>>,
+            lines: &[Line],
+            prefix: &str,
+            is_root: bool,
+        ) {
+            for (n, line) in lines.iter().enumerate() {
+                let is_last = n + 1 == lines.len();
+                let (own, nested) = if is_root {
+                    (String::new(), String::new())
+                } else if is_last {
+                    (
+                        format!("{}{}", prefix, table.symbols.right),
+                        format!(
+                            "{}{}",
+                            prefix,
+                            " ".repeat(table.symbols.right.chars().count())
+                        ),
+                    )
+                } else {
+                    (
+                        format!("{}{}", prefix, table.symbols.branch),
+                        format!("{}{}", prefix, table.symbols.vertical),
+                    )
+                };
+                let cells = table
+                    .columns
+                    .iter()
+                    .enumerate()
+                    .map(|(i, column)| {
+                        let text = table.cell_text(line, i);
+                        if column.tree {
+                            format!("{}{}", own, text)
+                        } else {
+                            text.to_string()
+                        }
+                    })
+                    .collect();
+                out.push(cells);
+                walk(table, out, &line.children, &nested, false);
+            }
+        }
+
+        let mut out = Vec::new();
+        walk(self, &mut out, &self.lines, "", true);
+        out
+    }
+
+    fn render_table(&self) -> String {
+        let rows = self.table_cells();
+
+        let mut widths: Vec<_> = self.columns.iter().map(Column::min_width).collect();
+        for row in &rows {
+            for (width, cell) in widths.iter_mut().zip(row) {
+                *width = (*width).max(cell.chars().count());
+            }
+        }
+        if let Some(width) = self.width {
+            self.shrink_columns(&mut widths, width);
+        }
+
+        let format_line = |values: &[String]| {
+            let cells: Vec<_> = self
+                .columns
+                .iter()
+                .zip(values)
+                .zip(&widths)
+                .map(|((column, value), width)| {
+                    if column.right {
+                        format!("{:>width$}", value)
+                    } else {
+                        format!("{:<width$}", value)
+                    }
+                })
+                .collect();
+            cells.join(" ").trim_end().to_string() + "\n"
+        };
+
+        let mut out = String::new();
+        if !self.noheadings {
+            let names: Vec<_> = self.columns.iter().map(|c| c.name.clone()).collect();
+            out.push_str(&format_line(&names));
+        }
+
+        for row in rows {
+            let cells: Vec<_> = self
+                .columns
+                .iter()
+                .zip(&row)
+                .zip(&widths)
+                .map(|((column, value), width)| fit_value(column, value, *width))
+                .collect();
+            let nlines = cells.iter().map(Vec::len).max().unwrap_or(1);
+
+            // Wrapped cells continue on the next lines, with the other columns left blank
+            for line in 0..nlines {
+                let values: Vec<_> = cells
+                    .iter()
+                    .map(|cell| cell.get(line).cloned().unwrap_or_default())
+                    .collect();
+                out.push_str(&format_line(&values));
+            }
+        }
+        out
+    }
+
+    /// Shrinks the columns that allow it, down to their minimum width, until the table fits in `width`.
+    fn shrink_columns(&self, widths: &mut [usize], width: usize) {
+        let total = widths.iter().sum::<usize>() + widths.len().saturating_sub(1);
+        let mut excess = total.saturating_sub(width);
+
+        for (i, column) in self.columns.iter().enumerate().rev() {
+            if excess == 0 {
+                break;
+            }
+            if column.overflow == Overflow::None {
+                continue;
+            }
+            let shrink = excess.min(widths[i].saturating_sub(column.min_width()));
+            widths[i] -= shrink;
+            excess -= shrink;
+        }
+    }
+}
+
+/// Splits a value into the lines it takes in a column of the given width.
+fn fit_value(column: &Column, value: &str, width: usize) -> Vec<String> {
+    if value.chars().count() <= width {
+        return vec![value.to_string()];
+    }
+
+    match column.overflow {
+        Overflow::None => vec![value.to_string()],
+        Overflow::Truncate => vec![value.chars().take(width).collect()],
+        // Like spaces in text, the `/` separators are dropped where the lines are broken
+        Overflow::Wrap => {
+            let mut lines = vec![];
+            let mut line = String::new();
+            for part in value.split('/') {
+                if !line.is_empty() && line.chars().count() + 1 + part.chars().count() > width {
+                    lines.push(std::mem::take(&mut line));
+                }
+                if !line.is_empty() {
+                    line.push('/');
+                }
+                line.push_str(part);
+                // A single part wider than the column has to be broken anywhere
+                while line.chars().count() > width {
+                    let rest = line.chars().skip(width).collect::<String>();
+                    lines.push(line.chars().take(width).collect());
+                    line = rest;
+                }
+            }
+            if !line.is_empty() {
+                lines.push(line);
+            }
+            lines
+        }
+    }
+}
+
+fn escape_hex(out: &mut String, c: char) {
+    let mut buf = [0; 4];
+    for byte in c.encode_utf8(&mut buf).bytes() {
+        out.push_str(&format!("\\x{:02x}", byte));
+    }
+}
+
+/// Raw output separates cells with spaces, so blanks within cells are escaped.
+fn escape_raw(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c.is_whitespace() || c.is_control() || c == '\\' {
+            escape_hex(&mut out, c);
+        } else {
+            out.push(c);
+        }
+    }
+    out
+}
+
+/// Pairs are meant to be evaluated by a shell, so the value is escaped the way a double-quoted string needs.
+fn escape_pair(value: &str) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '"' | '\\' | '`' | '$' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c if c.is_control() => escape_hex(&mut out, c),
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+fn sample_table(format: OutputFormat) -> Table {
+    let mut table = Table::new("things");
+    table.set_width(None);
+    table.set_format(format);
+    table.add_column(Column::new("NAME").tree(true));
+    table.add_column(Column::new("SIZE").right(true).json_type(JsonType::Number));
+    table.add_column(
+        Column::new("RO")
+            .json_type(JsonType::Boolean)
+            .placeholder("-"),
+    );
+
+    let mut root = Line::new([Some("root".to_string()), Some("42".to_string()), None]);
+    let mut child = Line::from(vec!["a b".to_string(), "7".to_string(), "no".to_string()]);
+    child.add_child(Line::from(vec![
+        "x\"$".to_string(),
+        "n/a".to_string(),
+        "1".to_string(),
+    ]));
+    root.add_child(child);
+    root.add_child(Line::from(vec![
+        "c".to_string(),
+        "1000".to_string(),
+        "yes".to_string(),
+    ]));
+    table.add_line(root);
+    table
+}
+
+#[test]
+fn test_render_table_tree() {
+    assert_eq!(
+        sample_table(OutputFormat::Table).render(),
+        "NAME    SIZE RO\n\
+         root      42 -\n\
+         ├─a b      7 no\n\
+         │ └─x\"$  n/a 1\n\
+         └─c     1000 yes\n"
+    );
+
+    let mut table = sample_table(OutputFormat::Table);
+    table.set_noheadings(true);
+    table.set_tree_symbols(TreeSymbols {
+        branch: "  ".to_string(),
+        vertical: "  ".to_string(),
+        right: "  ".to_string(),
+    });
+    assert_eq!(
+        table.render(),
+        "root      42 -\n  a b      7 no\n    x\"$  n/a 1\n  c     1000 yes\n"
+    );
+}
+
+#[test]
+fn test_render_raw_and_pairs() {
+    assert_eq!(
+        sample_table(OutputFormat::Raw).render(),
+        "NAME SIZE RO\nroot 42 -\na\\x20b 7 no\nx\"$ n/a 1\nc 1000 yes\n"
+    );
+    assert_eq!(
+        sample_table(OutputFormat::Pairs).render(),
+        "NAME=\"root\" SIZE=\"42\" RO=\"-\"\n\
+         NAME=\"a b\" SIZE=\"7\" RO=\"no\"\n\
+         NAME=\"x\\\"\\$\" SIZE=\"n/a\" RO=\"1\"\n\
+         NAME=\"c\" SIZE=\"1000\" RO=\"yes\"\n"
+    );
+}
+
+#[test]
+fn test_to_json() {
+    assert_eq!(
+        sample_table(OutputFormat::Json).to_json(),
+        serde_json::json!({
+            "things": [{
+                "name": "root",
+                "size": 42,
+                "ro": null,
+                "children": [
+                    {
+                        "name": "a b",
+                        "size": 7,
+                        "ro": false,
+                        "children": [{"name": "x\"$", "size": "n/a", "ro": true}],
+                    },
+                    {"name": "c", "size": 1000, "ro": true},
+                ],
+            }]
+        })
+    );
+}
+
+#[test]
+fn test_shrink_to_width() {
+    let mut table = Table::new("zones");
+    table.set_width(Some(13));
+    table.add_column(Column::new("BLOCK").right(true));
+    table.add_column(Column::new("ZONES").right(true).overflow(Overflow::Wrap));
+    table.add_column(Column::new("TYPE").overflow(Overflow::Truncate));
+    table.add_line(Line::from(vec![
+        "0-3".to_string(),
+        "DMA32/Normal/Movable".to_string(),
+        "online_movable".to_string(),
+    ]));
+
+    assert_eq!(
+        table.render(),
+        "BLOCK ZONES TYPE\n  0-3 DMA32 onli\n      Norma\n          l\n      Movab\n         le\n"
+    );
+}
+
+#[test]
+fn test_fit_value() {
+    let zones = Column::new("ZONES").overflow(Overflow::Wrap);
+    assert_eq!(
+        fit_value(&zones, "DMA32/Normal/Movable", 13),
+        ["DMA32/Normal", "Movable"]
+    );
+    assert_eq!(
+        fit_value(&zones, "DMA32/Normal", 5),
+        ["DMA32", "Norma", "l"]
+    );
+
+    let online_type = Column::new("ONLINE_TYPE").overflow(Overflow::Truncate);
+    assert_eq!(
+        fit_value(&online_type, "online_movable", 11),
+        ["online_mova"]
+    );
+    assert_eq!(fit_value(&Column::new("RANGE"), "0x0-0x1", 3), ["0x0-0x1"]);
+}
@@ -0,0 +1,482 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use clap::builder::{EnumValueParser, PossibleValue};
+use clap::{crate_version, Arg, ArgAction, ArgGroup, Command, ValueEnum};
+use std::fs;
+use std::io;
+use std::path::{Path, MAIN_SEPARATOR};
+use uu_lsmem::blocks::{self, MemoryBlock, MemoryState, ZoneId, PATH_SYS_MEMORY};
+use uu_lsmem::utils::size_to_human_string;
+use uucore::error::{set_exit_code, UResult, USimpleError};
+use uucore::parse_size::parse_size_u64;
+use uucore::{format_usage, help_about, help_usage, show_error};
+
+const ABOUT: &str = help_about!("chmem.md");
+const USAGE: &str = help_usage!("chmem.md");
+
+/// Same as util-linux, when only part of the requested memory could be changed.
+const EXIT_SOME_OK: i32 = 64;
+
+mod options {
+    pub const BLOCKS: &str = "blocks";
+    pub const DISABLE: &str = "disable";
+    pub const ENABLE: &str = "enable";
+    pub const RANGE: &str = "range";
+    pub const SYSROOT: &str = "sysroot";
+    pub const VERBOSE: &str = "verbose";
+    pub const ZONE: &str = "zone";
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Action {
+    Enable,
+    Disable,
+}
+
+impl Action {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Action::Enable => "enable",
+            Action::Disable => "disable",
+        }
+    }
+
+    fn get_past_tense(&self) -> &'static str {
+        match self {
+            Action::Enable => "enabled",
+            Action::Disable => "disabled",
+        }
+    }
+
+    fn target_state(&self) -> MemoryState {
+        match self {
+            Action::Enable => MemoryState::Online,
+            Action::Disable => MemoryState::Offline,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Zone {
+    Movable,
+    Normal,
+}
+
+impl ValueEnum for Zone {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Zone::Movable, Zone::Normal]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.get_name()))
+    }
+}
+
+impl Zone {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Zone::Movable => "Movable",
+            Zone::Normal => "Normal",
+        }
+    }
+
+    fn zone_id(&self) -> ZoneId {
+        match self {
+            Zone::Movable => ZoneId::ZoneMovable,
+            Zone::Normal => ZoneId::ZoneNormal,
+        }
+    }
+
+    /// The value written to the `state` attribute to online a block into this zone.
+    fn online_type(&self) -> &'static str {
+        match self {
+            Zone::Movable => "online_movable",
+            Zone::Normal => "online_kernel",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Request {
+    /// An amount of memory in bytes, picked from whichever blocks can be changed.
+    Size(u64),
+    /// An inclusive range of block indices.
+    Blocks(u64, u64),
+}
+
+struct Chmem {
+    sysmem: String,
+    block_size: u64,
+    blocks: Vec<MemoryBlock>,
+    have_zones: bool,
+    verbose: bool,
+    zone: Option<Zone>,
+}
+
+impl Chmem {
+    fn block_range(&self, blk: &MemoryBlock) -> String {
+        let start = blk.index * self.block_size;
+        format!("0x{:016x}-0x{:016x}", start, start + self.block_size - 1)
+    }
+
+    fn can_use_zone(&self, blk: &MemoryBlock) -> bool {
+        match self.zone {
+            Some(zone) if self.have_zones => blk.valid_zones().contains(&zone.zone_id()),
+            _ => true,
+        }
+    }
+
+    fn write_state(&self, blk: &MemoryBlock, action: Action) -> io::Result<()> {
+        let state = match (action, self.zone) {
+            (Action::Enable, Some(zone)) => zone.online_type(),
+            (Action::Enable, None) => "online",
+            (Action::Disable, _) => "offline",
+        };
+        let path = Path::new(&self.sysmem)
+            .join(format!("{}{}", blocks::PATH_NAME_MEMORY, blk.index))
+            .join(blocks::PATH_SUB_STATE);
+        fs::write(path, state)
+    }
+
+    /// Changes the state of a single block, reporting the outcome. Returns whether it succeeded.
+    fn change_block(&self, blk: &MemoryBlock, action: Action) -> bool {
+        match self.write_state(blk, action) {
+            Ok(()) => {
+                if self.verbose {
+                    println!(
+                        "Memory Block {} ({}) {}",
+                        blk.index,
+                        self.block_range(blk),
+                        action.get_past_tense()
+                    );
+                }
+                true
+            }
+            Err(e) => {
+                show_error!(
+                    "Memory Block {} ({}) {} failed: {}",
+                    blk.index,
+                    self.block_range(blk),
+                    action.get_name(),
+                    e
+                );
+                false
+            }
+        }
+    }
+
+    /// Changes blocks until the requested amount of memory is reached.
+    /// Memory is enabled from the lowest addresses up, and disabled from the highest addresses down.
+    fn change_size(&self, size: u64, action: Action) -> (u64, u64) {
+        let wanted = size / self.block_size;
+        let mut done = 0;
+
+        let candidates: Vec<_> = match action {
+            Action::Enable => self.blocks.iter().collect(),
+            Action::Disable => self.blocks.iter().rev().collect(),
+        };
+
+        for blk in candidates {
+            if done == wanted {
+                break;
+            }
+            let eligible = match action {
+                Action::Enable => blk.state == MemoryState::Offline && self.can_use_zone(blk),
+                Action::Disable => blk.state == MemoryState::Online && blk.removable,
+            };
+            if eligible && self.change_block(blk, action) {
+                done += 1;
+            }
+        }
+
+        if done < wanted {
+            show_error!(
+                "Could only {} {} of memory",
+                action.get_name(),
+                size_to_human_string(done * self.block_size)
+            );
+        }
+
+        (done, wanted)
+    }
+
+    fn change_blocks(&self, first: u64, last: u64, action: Action) -> (u64, u64) {
+        let mut done = 0;
+
+        // Nothing exists past the highest block, so a huge range isn't walked one missing block at a time
+        let highest = self.blocks.iter().map(|blk| blk.index).max();
+        let existing_last = highest.map(|highest| highest.min(last));
+        if let Some(existing_last) = existing_last {
+            for index in first..=existing_last {
+                let Some(blk) = self.blocks.iter().find(|blk| blk.index == index) else {
+                    self.report_missing(index, index);
+                    continue;
+                };
+                if self.change_existing_block(blk, action) {
+                    done += 1;
+                }
+            }
+        }
+        match existing_last {
+            Some(existing_last) if existing_last >= last => {}
+            Some(existing_last) => self.report_missing(first.max(existing_last + 1), last),
+            None => self.report_missing(first, last),
+        }
+
+        (done, (last - first).saturating_add(1))
+    }
+
+    fn report_missing(&self, first: u64, last: u64) {
+        let start = first.checked_mul(self.block_size);
+        let end = last
+            .checked_add(1)
+            .and_then(|next| next.checked_mul(self.block_size));
+        let range = match (start, end) {
+            (Some(start), Some(end)) => format!(" (0x{:016x}-0x{:016x})", start, end - 1),
+            _ => String::new(),
+        };
+        if first == last {
+            show_error!("Memory Block {}{} does not exist", first, range);
+        } else {
+            show_error!("Memory Blocks {}-{}{} do not exist", first, last, range);
+        }
+    }
+
+    /// Returns whether the block ends up in the target state.
+    fn change_existing_block(&self, blk: &MemoryBlock, action: Action) -> bool {
+        if blk.state == action.target_state() {
+            if self.verbose {
+                println!(
+                    "Memory Block {} ({}) already {}",
+                    blk.index,
+                    self.block_range(blk),
+                    action.get_past_tense()
+                );
+            }
+            return true;
+        }
+
+        if action == Action::Enable && !self.can_use_zone(blk) {
+            show_error!(
+                "Memory Block {} ({}) cannot be enabled in zone {}",
+                blk.index,
+                self.block_range(blk),
+                self.zone.map(|zone| zone.get_name()).unwrap_or_default()
+            );
+            return false;
+        }
+
+        self.change_block(blk, action)
+    }
+}
+
+fn parse_address(value: &str) -> Option<u64> {
+    match value
+        .strip_prefix("0x")
+        .or_else(|| value.strip_prefix("0X"))
+    {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+fn parse_request(value: &str, use_blocks: bool, block_size: u64) -> UResult<Request> {
+    if use_blocks {
+        let (first, last) = value.split_once('-').unwrap_or((value, value));
+        return match (first.parse::<u64>(), last.parse::<u64>()) {
+            (Ok(first), Ok(last)) if first <= last => Ok(Request::Blocks(first, last)),
+            _ => Err(USimpleError::new(
+                1,
+                format!("invalid block range: '{}'", value),
+            )),
+        };
+    }
+
+    if let Some((start, end)) = value.split_once('-') {
+        let (Some(start), Some(end)) = (parse_address(start), parse_address(end)) else {
+            return Err(USimpleError::new(1, format!("invalid range: '{}'", value)));
+        };
+        // A range ending at the very last address can't be aligned, as its end doesn't fit in 64 bits
+        let end_aligned = end
+            .checked_add(1)
+            .is_some_and(|next| next % block_size == 0);
+        if start > end || start % block_size != 0 || !end_aligned {
+            return Err(USimpleError::new(
+                1,
+                format!(
+                    "invalid range: '{}': start and end must be aligned to the memory block size ({})",
+                    value,
+                    size_to_human_string(block_size)
+                ),
+            ));
+        }
+        return Ok(Request::Blocks(start / block_size, end / block_size));
+    }
+
+    let size = parse_size_u64(value)
+        .map_err(|_| USimpleError::new(1, format!("invalid size: '{}'", value)))?;
+    if size == 0 || size % block_size != 0 {
+        return Err(USimpleError::new(
+            1,
+            format!(
+                "invalid size: '{}': must be a multiple of the memory block size ({})",
+                value,
+                size_to_human_string(block_size)
+            ),
+        ));
+    }
+    Ok(Request::Size(size))
+}
+
+#[uucore::main]
+pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let matches: clap::ArgMatches = uu_app().try_get_matches_from(args)?;
+
+    let action = if matches.get_flag(options::ENABLE) {
+        Action::Enable
+    } else {
+        Action::Disable
+    };
+
+    let mut sysmem = PATH_SYS_MEMORY.to_string();
+    if let Some(sysroot) = matches.get_one::<String>(options::SYSROOT) {
+        sysmem = format!(
+            "{}{}{}",
+            sysroot.trim_end_matches(MAIN_SEPARATOR),
+            MAIN_SEPARATOR,
+            sysmem.trim_start_matches(MAIN_SEPARATOR)
+        );
+    }
+
+    let block_size = blocks::read_block_size(&sysmem)?;
+    let paths = blocks::get_block_paths(&sysmem)?;
+    let (have_nodes, have_zones) = blocks::detect_nodes_and_zones(&paths);
+
+    let chmem = Chmem {
+        blocks: paths
+            .iter()
+            .map(|path| blocks::memory_block_read_attrs(path, have_nodes, have_zones))
+            .collect::<UResult<_>>()?,
+        sysmem,
+        block_size,
+        have_zones,
+        verbose: matches.get_flag(options::VERBOSE),
+        zone: matches.get_one::<Zone>(options::ZONE).copied(),
+    };
+
+    let value = matches.get_one::<String>(options::RANGE).unwrap();
+    let (done, wanted) = match parse_request(value, matches.get_flag(options::BLOCKS), block_size)?
+    {
+        Request::Size(size) => chmem.change_size(size, action),
+        Request::Blocks(first, last) => chmem.change_blocks(first, last, action),
+    };
+
+    if done == 0 {
+        set_exit_code(1);
+    } else if done < wanted {
+        set_exit_code(EXIT_SOME_OK);
+    }
+
+    Ok(())
+}
+
+pub fn uu_app() -> Command {
+    Command::new(uucore::util_name())
+        .version(crate_version!())
+        .about(ABOUT)
+        .override_usage(format_usage(USAGE))
+        .infer_long_args(true)
+        .arg(
+            Arg::new(options::ENABLE)
+                .short('e')
+                .long("enable")
+                .help("enable memory")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::DISABLE)
+                .short('d')
+                .long("disable")
+                .help("disable memory")
+                .action(ArgAction::SetTrue),
+        )
+        .group(
+            ArgGroup::new("action")
+                .args([options::ENABLE, options::DISABLE])
+                .required(true),
+        )
+        .arg(
+            Arg::new(options::BLOCKS)
+                .short('b')
+                .long("blocks")
+                .help("use memory blocks")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::ZONE)
+                .short('z')
+                .long("zone")
+                .help("select memory zone")
+                .ignore_case(true)
+                .action(ArgAction::Set)
+                .value_name("name")
+                .value_parser(EnumValueParser::<Zone>::new())
+                .conflicts_with(options::DISABLE),
+        )
+        .arg(
+            Arg::new(options::SYSROOT)
+                .short('s')
+                .long("sysroot")
+                .help("use the specified directory as system root")
+                .action(ArgAction::Set)
+                .value_name("dir"),
+        )
+        .arg(
+            Arg::new(options::VERBOSE)
+                .short('v')
+                .long("verbose")
+                .help("verbose output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::RANGE)
+                .value_name("SIZE|RANGE|BLOCKRANGE")
+                .required(true),
+        )
+}
+
+#[test]
+fn test_parse_request() {
+    const BLOCK_SIZE: u64 = 128 * 1024 * 1024;
+
+    assert_eq!(
+        parse_request("256M", false, BLOCK_SIZE).unwrap(),
+        Request::Size(2 * BLOCK_SIZE)
+    );
+    assert_eq!(
+        parse_request("0x10000000-0x17ffffff", false, BLOCK_SIZE).unwrap(),
+        Request::Blocks(2, 2)
+    );
+    assert_eq!(
+        parse_request("268435456-536870911", false, BLOCK_SIZE).unwrap(),
+        Request::Blocks(2, 3)
+    );
+    assert_eq!(
+        parse_request("3-5", true, BLOCK_SIZE).unwrap(),
+        Request::Blocks(3, 5)
+    );
+    assert_eq!(
+        parse_request("7", true, BLOCK_SIZE).unwrap(),
+        Request::Blocks(7, 7)
+    );
+
+    assert!(parse_request("100M", false, BLOCK_SIZE).is_err());
+    assert!(parse_request("0", false, BLOCK_SIZE).is_err());
+    assert!(parse_request("0x10000000-0x17000000", false, BLOCK_SIZE).is_err());
+    assert!(parse_request("5-3", true, BLOCK_SIZE).is_err());
+    assert!(parse_request("0x0-0xffffffffffffffff", false, BLOCK_SIZE).is_err());
+    assert!(parse_request("a", true, BLOCK_SIZE).is_err());
+}
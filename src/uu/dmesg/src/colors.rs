@@ -0,0 +1,146 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Output colors, configurable with terminal-colors.d(5) files like in util-linux.
+//!
+//! In `auto` mode, colors are disabled by a `dmesg.disable` or `disable` file, unless there is
+//! also a `dmesg.enable` file. A `dmesg.scheme` file overrides the default colors, with one
+//! `name color` pair per line, where the color is a name such as `red` or `bold`, or the
+//! parameters of an SGR escape sequence such as `1;31`.
+
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum ColorMode {
+    Auto,
+    Always,
+    Never,
+}
+
+pub struct ColorScheme {
+    colors: HashMap<String, String>,
+}
+
+const DEFAULT_SCHEME: &[(&str, &str)] = &[
+    ("subsys", "brown"),
+    ("time", "green"),
+    ("timebreak", "green bold"),
+    ("alert", "reverse red"),
+    ("crit", "bold red"),
+    ("err", "red"),
+    ("warn", "yellow"),
+];
+
+const COLOR_NAMES: &[(&str, &str)] = &[
+    ("black", "30"),
+    ("blink", "5"),
+    ("blue", "34"),
+    ("bold", "1"),
+    ("brown", "33"),
+    ("cyan", "36"),
+    ("darkgray", "1;30"),
+    ("gray", "37"),
+    ("green", "32"),
+    ("halfbright", "2"),
+    ("lightblue", "1;34"),
+    ("lightcyan", "1;36"),
+    ("lightgray", "37"),
+    ("lightgreen", "1;32"),
+    ("lightmagenta", "1;35"),
+    ("lightred", "1;31"),
+    ("magenta", "35"),
+    ("red", "31"),
+    ("reset", "0"),
+    ("reverse", "7"),
+    ("yellow", "1;33"),
+    ("white", "1;37"),
+];
+
+impl ColorScheme {
+    /// The colors of `util`, or `None` if the output shouldn't be colored.
+    pub fn load(util: &str, mode: ColorMode, is_terminal: bool) -> Option<Self> {
+        let dirs = config_dirs();
+        let has_file = |name: &str| dirs.iter().any(|dir| dir.join(name).exists());
+        match mode {
+            ColorMode::Never => return None,
+            ColorMode::Auto if !is_terminal => return None,
+            ColorMode::Auto
+                if (has_file(&format!("{util}.disable")) || has_file("disable"))
+                    && !has_file(&format!("{util}.enable")) =>
+            {
+                return None
+            }
+            _ => (),
+        }
+
+        let mut colors: HashMap<_, _> = DEFAULT_SCHEME
+            .iter()
+            .filter_map(|(name, color)| Some((name.to_string(), parse_color(color)?)))
+            .collect();
+
+        // The user's scheme is applied last, so it overrides the system one
+        for dir in dirs.iter().rev() {
+            let Ok(content) = fs::read_to_string(dir.join(format!("{util}.scheme"))) else {
+                continue;
+            };
+            for line in content.lines() {
+                let line = line.trim();
+                if line.is_empty() || line.starts_with('#') {
+                    continue;
+                }
+                if let Some((name, color)) = line.split_once(char::is_whitespace) {
+                    if let Some(sequence) = parse_color(color.trim()) {
+                        colors.insert(name.to_string(), sequence);
+                    }
+                }
+            }
+        }
+
+        Some(Self { colors })
+    }
+
+    /// `text` in the color called `name`, or unchanged if there is no such color.
+    pub fn paint(&self, name: &str, text: &str) -> String {
+        match self.colors.get(name) {
+            Some(sequence) if !text.is_empty() => format!("{sequence}{text}\x1b[0m"),
+            _ => text.to_string(),
+        }
+    }
+}
+
+/// The user's directory comes first.
+fn config_dirs() -> Vec<PathBuf> {
+    let mut dirs = vec![];
+    if let Some(config) = env::var_os("XDG_CONFIG_HOME") {
+        dirs.push(PathBuf::from(config).join("terminal-colors.d"));
+    } else if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".config/terminal-colors.d"));
+    }
+    dirs.push(PathBuf::from("/etc/terminal-colors.d"));
+    dirs
+}
+
+/// Turns color names separated by spaces, or SGR parameters, into an escape sequence.
+fn parse_color(color: &str) -> Option<String> {
+    let params = if color.chars().all(|c| c.is_ascii_digit() || c == ';') {
+        color.to_string()
+    } else {
+        color
+            .split_whitespace()
+            .map(|name| {
+                COLOR_NAMES
+                    .iter()
+                    .find(|(n, _)| *n == name)
+                    .map(|(_, params)| *params)
+            })
+            .collect::<Option<Vec<_>>>()?
+            .join(";")
+    };
+    if params.is_empty() {
+        None
+    } else {
+        Some(format!("\x1b[{params}m"))
+    }
+}
@@ -3,15 +3,15 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use chrono::{DateTime, FixedOffset};
-use clap::{crate_version, Arg, ArgAction, Command};
+use clap::{crate_version, value_parser, Arg, ArgAction, Command};
 use regex::Regex;
 use std::{
     collections::HashSet,
-    fs::File,
     fs::OpenOptions,
     hash::Hash,
-    io::{BufRead, BufReader, ErrorKind},
+    io::IsTerminal,
+    io::{BufRead, BufReader, Cursor, ErrorKind, Read},
+    str::FromStr,
     sync::OnceLock,
 };
 
@@ -26,19 +26,76 @@ use uucore::{
 #[cfg(not(target_os = "windows"))]
 use uucore::libc;
 
+mod colors;
+mod grep;
 mod json;
+mod logfmt;
+#[cfg(unix)]
+mod pager;
+mod range;
+mod rfc5424;
+mod syslog;
 mod time_formatter;
 
 const ABOUT: &str = help_about!("dmesg.md");
 const USAGE: &str = help_usage!("dmesg.md");
 
+const KMSG_DEVICE: &str = "/dev/kmsg";
+
+// Size of the buffer a read(2) from /dev/kmsg needs, CONSOLE_EXT_LOG_MAX in the kernel
+const KMSG_RECORD_MAX: usize = 8192;
+
 #[uucore::main]
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let mut dmesg = Dmesg::new();
     let matches: clap::ArgMatches = uu_app().try_get_matches_from(args)?;
+    if matches.get_flag(options::CLEAR) {
+        return syslog::clear().map_err_context(|| "klogctl failed".to_string());
+    }
+    if matches.get_flag(options::CONSOLE_OFF) {
+        return syslog::console_off().map_err_context(|| "klogctl failed".to_string());
+    }
+    if matches.get_flag(options::CONSOLE_ON) {
+        return syslog::console_on().map_err_context(|| "klogctl failed".to_string());
+    }
+    if let Some(level) = matches.get_one::<String>(options::CONSOLE_LEVEL) {
+        let level = parse_console_level(level)?;
+        return syslog::set_console_level(level).map_err_context(|| "klogctl failed".to_string());
+    }
     if matches.get_flag(options::JSON) {
         dmesg.output_format = OutputFormat::Json;
     }
+    if let Some(output_format) = matches.get_one::<String>(options::OUTPUT_FORMAT) {
+        dmesg.output_format = match output_format.as_str() {
+            "ndjson" => OutputFormat::Ndjson,
+            "logfmt" => OutputFormat::Logfmt,
+            "rfc5424" => OutputFormat::Rfc5424,
+            // The value parser only accepts the formats above
+            _ => unreachable!(),
+        };
+    }
+    dmesg.decode = matches.get_flag(options::DECODE);
+    dmesg.raw = matches.get_flag(options::RAW);
+    let human = matches.get_flag(options::HUMAN);
+    if human {
+        dmesg.time_format = TimeFormat::Reltime;
+        dmesg.pager = !matches.get_flag(options::NOPAGER);
+    }
+    let color_mode = match matches
+        .get_one::<String>(options::COLOR)
+        .map(String::as_str)
+    {
+        Some("always") => colors::ColorMode::Always,
+        Some("never") => colors::ColorMode::Never,
+        Some(_) => colors::ColorMode::Auto,
+        None if human => colors::ColorMode::Auto,
+        None => colors::ColorMode::Never,
+    };
+    // The raw output is meant to be parsed
+    if !dmesg.raw {
+        dmesg.colors =
+            colors::ColorScheme::load("dmesg", color_mode, std::io::stdout().is_terminal());
+    }
     if let Some(time_format) = matches.get_one::<String>(options::TIME_FORMAT) {
         dmesg.time_format = match &time_format[..] {
             "delta" => TimeFormat::Delta,
@@ -55,71 +112,83 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
             }
         };
     }
+    let mut facility_filters = HashSet::new();
     if let Some(list_args) = matches.get_many::<String>(options::FACILITY) {
-        let mut facility_filters = HashSet::new();
         for list in list_args {
             for arg in list.split(',') {
-                let facility = match arg {
-                    "kern" => Facility::Kern,
-                    "user" => Facility::User,
-                    "mail" => Facility::Mail,
-                    "daemon" => Facility::Daemon,
-                    "auth" => Facility::Auth,
-                    "syslog" => Facility::Syslog,
-                    "lpr" => Facility::Lpr,
-                    "news" => Facility::News,
-                    "uucp" => Facility::Uucp,
-                    "cron" => Facility::Cron,
-                    "authpriv" => Facility::Authpriv,
-                    "ftp" => Facility::Ftp,
-                    "res0" => Facility::Res0,
-                    "res1" => Facility::Res1,
-                    "res2" => Facility::Res2,
-                    "res3" => Facility::Res3,
-                    "local0" => Facility::Local0,
-                    "local1" => Facility::Local1,
-                    "local2" => Facility::Local2,
-                    "local3" => Facility::Local3,
-                    "local4" => Facility::Local4,
-                    "local5" => Facility::Local5,
-                    "local6" => Facility::Local6,
-                    "local7" => Facility::Local7,
-                    _ => return Err(USimpleError::new(1, format!("unknown facility '{arg}'"))),
-                };
+                let facility = Facility::from_str(arg)
+                    .map_err(|_| USimpleError::new(1, format!("unknown facility '{arg}'")))?;
                 facility_filters.insert(facility);
             }
         }
+    }
+    if matches.get_flag(options::KERNEL) {
+        facility_filters.insert(Facility::Kern);
+    }
+    if matches.get_flag(options::USERSPACE) {
+        facility_filters.extend(Facility::all().filter(|facility| *facility != Facility::Kern));
+    }
+    if !facility_filters.is_empty() {
         dmesg.facility_filters = Some(facility_filters);
     }
     if let Some(list_args) = matches.get_many::<String>(options::LEVEL) {
         let mut level_filters = HashSet::new();
         for list in list_args {
             for arg in list.split(',') {
-                let level = match arg {
-                    "emerg" => Level::Emerg,
-                    "alert" => Level::Alert,
-                    "crit" => Level::Crit,
-                    "err" => Level::Err,
-                    "warn" => Level::Warn,
-                    "notice" => Level::Notice,
-                    "info" => Level::Info,
-                    "debug" => Level::Debug,
-                    _ => return Err(USimpleError::new(1, format!("unknown level '{arg}'"))),
-                };
+                let level = Level::from_str(arg)
+                    .map_err(|_| USimpleError::new(1, format!("unknown level '{arg}'")))?;
                 level_filters.insert(level);
             }
         }
         dmesg.level_filters = Some(level_filters);
     }
-    if let Some(since) = matches.get_one::<String>(options::SINCE) {
-        dmesg.since_filter = Some(time_formatter::parse_datetime(since)?);
+    dmesg.time_range = range::TimeRange::parse(
+        matches
+            .get_one::<String>(options::SINCE)
+            .map(String::as_str),
+        matches
+            .get_one::<String>(options::UNTIL)
+            .map(String::as_str),
+    )?;
+    dmesg.sequence_range = range::Range::new(
+        matches.get_one::<u64>(options::SINCE_SEQ).copied(),
+        matches.get_one::<u64>(options::UNTIL_SEQ).copied(),
+    );
+    if let Some(list_args) = matches.get_many::<String>(options::SUBSYSTEM) {
+        dmesg.subsystem_filters = Some(parse_list(list_args));
+    }
+    if let Some(list_args) = matches.get_many::<String>(options::DEVICE) {
+        dmesg.device_filters = Some(parse_list(list_args));
     }
-    if let Some(until) = matches.get_one::<String>(options::UNTIL) {
-        dmesg.until_filter = Some(time_formatter::parse_datetime(until)?);
+    dmesg.message_filter = grep::MessageFilter::new(
+        matches
+            .get_many::<String>(options::GREP)
+            .unwrap_or_default(),
+        matches
+            .get_many::<String>(options::EXCLUDE)
+            .unwrap_or_default(),
+        matches.get_flag(options::IGNORE_CASE),
+        matches
+            .get_one::<usize>(options::BEFORE_CONTEXT)
+            .copied()
+            .unwrap_or(0),
+        matches
+            .get_one::<usize>(options::AFTER_CONTEXT)
+            .copied()
+            .unwrap_or(0),
+    )?;
+    if matches.get_flag(options::FOLLOW_NEW) {
+        dmesg.follow = true;
+        dmesg.follow_new = true;
+    } else if matches.get_flag(options::FOLLOW) {
+        dmesg.follow = true;
     }
+    dmesg.read_clear = matches.get_flag(options::READ_CLEAR);
+    dmesg.syslog = matches.get_flag(options::SYSLOG) || dmesg.read_clear;
+    dmesg.buffer_size = matches.get_one::<usize>(options::BUFFER_SIZE).copied();
     if let Some(kmsg_file) = matches.get_one::<String>(options::KMSG_FILE) {
         dmesg.kmsg_file = kmsg_file;
-        dmesg.kmsg_record_separator = 0;
+        dmesg.kmsg_record_separator = Some(0);
     } else if cfg!(target_os = "windows") {
         return Err(USimpleError::new(1, "Windows requires the use of '-K'"));
     }
@@ -127,6 +196,27 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     Ok(())
 }
 
+fn parse_list<'a>(list_args: impl Iterator<Item = &'a String>) -> HashSet<String> {
+    list_args
+        .flat_map(|list| list.split(','))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Parses the level given to `--console-level`, either a number or the name of the least
+/// severe level to print, so that `1` and `emerg` are the same.
+fn parse_console_level(s: &str) -> UResult<u32> {
+    let level = match s.parse::<u32>() {
+        Ok(level) => level,
+        Err(_) => Level::from_str(s).map_or(0, |level| level as u32 + 1),
+    };
+    if (1..=8).contains(&level) {
+        Ok(level)
+    } else {
+        Err(USimpleError::new(1, format!("unknown level '{s}'")))
+    }
+}
+
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
         .override_usage(format_usage(USAGE))
@@ -137,6 +227,71 @@ pub fn uu_app() -> Command {
                 .short('K')
                 .long("kmsg-file")
                 .help("use the file in kmsg format")
+                .action(ArgAction::Set)
+                .conflicts_with_all([options::SYSLOG, options::READ_CLEAR]),
+        )
+        .arg(
+            Arg::new(options::SYSLOG)
+                .short('S')
+                .long("syslog")
+                .help("force to use syslog(2) rather than /dev/kmsg")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::FOLLOW, options::FOLLOW_NEW]),
+        )
+        .arg(
+            Arg::new(options::READ_CLEAR)
+                .short('c')
+                .long("read-clear")
+                .help("read and clear all messages, implies --syslog")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::FOLLOW, options::FOLLOW_NEW]),
+        )
+        .arg(
+            Arg::new(options::CLEAR)
+                .short('C')
+                .long("clear")
+                .help("clear the kernel ring buffer")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([
+                    options::READ_CLEAR,
+                    options::KMSG_FILE,
+                    options::JSON,
+                    options::FOLLOW,
+                    options::FOLLOW_NEW,
+                ]),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_OFF)
+                .short('D')
+                .long("console-off")
+                .help("disable printing messages to console")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::CONSOLE_ON, options::CONSOLE_LEVEL, options::CLEAR]),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_ON)
+                .short('E')
+                .long("console-on")
+                .help("enable printing messages to console")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::CONSOLE_LEVEL, options::CLEAR]),
+        )
+        .arg(
+            Arg::new(options::CONSOLE_LEVEL)
+                .short('n')
+                .long("console-level")
+                .help("set level of messages printed to console")
+                .value_name("level")
+                .action(ArgAction::Set)
+                .conflicts_with(options::CLEAR),
+        )
+        .arg(
+            Arg::new(options::BUFFER_SIZE)
+                .short('s')
+                .long("buffer-size")
+                .help("buffer size to query the kernel ring buffer")
+                .value_name("size")
+                .value_parser(value_parser!(usize))
                 .action(ArgAction::Set),
         )
         .arg(
@@ -144,7 +299,17 @@ pub fn uu_app() -> Command {
                 .short('J')
                 .long("json")
                 .help("use JSON output format")
-                .action(ArgAction::SetTrue),
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::FOLLOW, options::FOLLOW_NEW]),
+        )
+        .arg(
+            Arg::new(options::OUTPUT_FORMAT)
+                .long("output-format")
+                .help("use one line per message in the given format:\n  [ndjson|logfmt|rfc5424]")
+                .value_name("format")
+                .value_parser(["ndjson", "logfmt", "rfc5424"])
+                .conflicts_with(options::JSON)
+                .action(ArgAction::Set),
         )
         .arg(
             Arg::new(options::TIME_FORMAT)
@@ -155,6 +320,61 @@ pub fn uu_app() -> Command {
                 )
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new(options::DECODE)
+                .short('x')
+                .long("decode")
+                .help("decode facility and level to readable string")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::RAW)
+                .short('r')
+                .long("raw")
+                .help("print the raw message buffer")
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::DECODE, options::JSON, options::OUTPUT_FORMAT]),
+        )
+        .arg(
+            Arg::new(options::KERNEL)
+                .short('k')
+                .long("kernel")
+                .help("display kernel messages")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::USERSPACE)
+                .short('u')
+                .long("userspace")
+                .help("display userspace messages")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::HUMAN)
+                .short('H')
+                .long("human")
+                .help("human readable output")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::COLOR)
+                .short('L')
+                .long("color")
+                .help("colorize messages (auto, always or never)")
+                .value_name("when")
+                .value_parser(["auto", "always", "never"])
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("auto")
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(options::NOPAGER)
+                .short('P')
+                .long("nopager")
+                .help("do not pipe output into a pager")
+                .action(ArgAction::SetTrue),
+        )
         .arg(
             Arg::new(options::FACILITY)
                 .short('f')
@@ -173,61 +393,230 @@ pub fn uu_app() -> Command {
             Arg::new(options::SINCE)
                 .long("since")
                 .help("display the lines since the specified time")
+                .allow_hyphen_values(true)
                 .action(ArgAction::Set),
         )
         .arg(
             Arg::new(options::UNTIL)
                 .long("until")
                 .help("display the lines until the specified time")
+                .allow_hyphen_values(true)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(options::SINCE_SEQ)
+                .long("since-seq")
+                .value_name("NUMBER")
+                .help("display the lines since the specified sequence number")
+                .value_parser(value_parser!(u64))
+                .conflicts_with_all([options::SYSLOG, options::READ_CLEAR])
                 .action(ArgAction::Set),
         )
+        .arg(
+            Arg::new(options::UNTIL_SEQ)
+                .long("until-seq")
+                .value_name("NUMBER")
+                .help("display the lines until the specified sequence number")
+                .value_parser(value_parser!(u64))
+                .conflicts_with_all([options::SYSLOG, options::READ_CLEAR])
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(options::SUBSYSTEM)
+                .long("subsystem")
+                .help("restrict output to defined subsystems")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::DEVICE)
+                .long("device")
+                .help("restrict output to defined devices")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::GREP)
+                .long("grep")
+                .value_name("PATTERN")
+                .help("display the lines whose message matches the regular expression")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::EXCLUDE)
+                .long("exclude")
+                .value_name("PATTERN")
+                .help("hide the lines whose message matches the regular expression")
+                .action(ArgAction::Append),
+        )
+        .arg(
+            Arg::new(options::IGNORE_CASE)
+                .short('i')
+                .long("ignore-case")
+                .help("ignore case in --grep and --exclude patterns")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::AFTER_CONTEXT)
+                .short('A')
+                .long("after-context")
+                .value_name("NUM")
+                .help("display NUM lines after the lines matching --grep")
+                .value_parser(value_parser!(usize))
+                .requires(options::GREP)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(options::BEFORE_CONTEXT)
+                .short('B')
+                .long("before-context")
+                .value_name("NUM")
+                .help("display NUM lines before the lines matching --grep")
+                .value_parser(value_parser!(usize))
+                .requires(options::GREP)
+                .action(ArgAction::Set),
+        )
+        .arg(
+            Arg::new(options::FOLLOW)
+                .short('w')
+                .long("follow")
+                .help("wait for new messages")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::FOLLOW_NEW)
+                .short('W')
+                .long("follow-new")
+                .help("wait and print only new messages")
+                .action(ArgAction::SetTrue),
+        )
 }
 
 mod options {
     pub const KMSG_FILE: &str = "kmsg-file";
     pub const JSON: &str = "json";
+    pub const OUTPUT_FORMAT: &str = "output-format";
     pub const TIME_FORMAT: &str = "time-format";
+    pub const DECODE: &str = "decode";
+    pub const RAW: &str = "raw";
+    pub const KERNEL: &str = "kernel";
+    pub const USERSPACE: &str = "userspace";
+    pub const HUMAN: &str = "human";
+    pub const COLOR: &str = "color";
+    pub const NOPAGER: &str = "nopager";
     pub const FACILITY: &str = "facility";
     pub const LEVEL: &str = "level";
     pub const SINCE: &str = "since";
     pub const UNTIL: &str = "until";
+    pub const SINCE_SEQ: &str = "since-seq";
+    pub const UNTIL_SEQ: &str = "until-seq";
+    pub const SUBSYSTEM: &str = "subsystem";
+    pub const DEVICE: &str = "device";
+    pub const GREP: &str = "grep";
+    pub const EXCLUDE: &str = "exclude";
+    pub const IGNORE_CASE: &str = "ignore-case";
+    pub const AFTER_CONTEXT: &str = "after-context";
+    pub const BEFORE_CONTEXT: &str = "before-context";
+    pub const FOLLOW: &str = "follow";
+    pub const FOLLOW_NEW: &str = "follow-new";
+    pub const SYSLOG: &str = "syslog";
+    pub const READ_CLEAR: &str = "read-clear";
+    pub const CLEAR: &str = "clear";
+    pub const BUFFER_SIZE: &str = "buffer-size";
+    pub const CONSOLE_OFF: &str = "console-off";
+    pub const CONSOLE_ON: &str = "console-on";
+    pub const CONSOLE_LEVEL: &str = "console-level";
 }
 
 struct Dmesg<'a> {
     kmsg_file: &'a str,
-    kmsg_record_separator: u8,
+    /// `None` when every read(2) returns a whole record, as with /dev/kmsg
+    kmsg_record_separator: Option<u8>,
     output_format: OutputFormat,
     time_format: TimeFormat,
     facility_filters: Option<HashSet<Facility>>,
     level_filters: Option<HashSet<Level>>,
-    since_filter: Option<chrono::DateTime<chrono::FixedOffset>>,
-    until_filter: Option<chrono::DateTime<chrono::FixedOffset>>,
+    time_range: range::TimeRange,
+    /// Only records read from `kmsg_file` have a sequence number
+    sequence_range: range::Range<u64>,
+    subsystem_filters: Option<HashSet<String>>,
+    device_filters: Option<HashSet<String>>,
+    message_filter: grep::MessageFilter,
+    /// Wait for new records instead of stopping at the end of the buffer
+    follow: bool,
+    /// Skip the records already in the buffer, only with `follow`
+    follow_new: bool,
+    /// Read the ring buffer with syslog(2) instead of `kmsg_file`
+    syslog: bool,
+    /// Clear the ring buffer after reading it, only with `syslog`
+    read_clear: bool,
+    /// Number of bytes to read with syslog(2), defaults to the size of the ring buffer
+    buffer_size: Option<usize>,
+    /// Show the facility and level of the records by name
+    decode: bool,
+    /// Prefix the records with their priority, the way the kernel logs them
+    raw: bool,
+    /// `None` when the output isn't colored
+    colors: Option<colors::ColorScheme>,
+    /// Pipe the output into a pager, if stdout is a terminal
+    pager: bool,
 }
 
 impl Dmesg<'_> {
     fn new() -> Self {
         Dmesg {
-            kmsg_file: "/dev/kmsg",
-            kmsg_record_separator: b'\n',
+            kmsg_file: KMSG_DEVICE,
+            kmsg_record_separator: None,
             output_format: OutputFormat::Normal,
             time_format: TimeFormat::Raw,
             facility_filters: None,
             level_filters: None,
-            since_filter: None,
-            until_filter: None,
+            time_range: range::Range::new(None, None),
+            sequence_range: range::Range::new(None, None),
+            subsystem_filters: None,
+            device_filters: None,
+            message_filter: grep::MessageFilter::default(),
+            follow: false,
+            follow_new: false,
+            syslog: false,
+            read_clear: false,
+            buffer_size: None,
+            decode: false,
+            raw: false,
+            colors: None,
+            pager: false,
         }
     }
 
     fn print(&self) -> UResult<()> {
+        #[cfg(unix)]
+        let _pager = if self.pager {
+            pager::Pager::start()
+        } else {
+            None
+        };
         match self.output_format {
             OutputFormat::Json => self.print_json(),
+            OutputFormat::Ndjson => {
+                self.print_lines(|record| Ok(json::serialize_record(record, self.decode)))
+            }
+            OutputFormat::Logfmt => {
+                self.print_lines(|record| Ok(logfmt::format_record(record, self.decode)))
+            }
+            OutputFormat::Rfc5424 => self.print_lines(rfc5424::format_record),
             OutputFormat::Normal => self.print_normal(),
         }
     }
 
+    /// For the formats with one line per record, which are printed as the records are read.
+    fn print_lines(&self, format: impl Fn(&Record) -> UResult<String>) -> UResult<()> {
+        for record in self.try_filtered_iter()? {
+            println!("{}", format(&record?)?);
+        }
+        Ok(())
+    }
+
     fn print_json(&self) -> UResult<()> {
         let records: UResult<Vec<Record>> = self.try_filtered_iter()?.collect();
-        println!("{}", json::serialize_records(&records?));
+        println!("{}", json::serialize_records(&records?, self.decode));
         Ok(())
     }
 
@@ -236,44 +625,125 @@ impl Dmesg<'_> {
         let mut delta_formatter = time_formatter::DeltaFormatter::new();
         for record in self.try_filtered_iter()? {
             let record = record?;
-            match self.time_format {
+            if self.raw {
+                print!("<{}>", record.priority_facility);
+            } else if self.decode {
+                print!(
+                    "{:<6}:{:<6}: ",
+                    Facility::from(record.priority_facility).name(),
+                    Level::from(record.priority_facility).name()
+                );
+            }
+            let timestamp = match self.time_format {
                 TimeFormat::Delta => {
-                    print!("[{}] ", delta_formatter.format(record.timestamp_us))
-                }
-                TimeFormat::Reltime => {
-                    print!("[{}] ", reltime_formatter.format(record.timestamp_us))
+                    Some(format!("[{}]", delta_formatter.format(record.timestamp_us)))
                 }
+                TimeFormat::Reltime => Some(format!(
+                    "[{}]",
+                    reltime_formatter.format(record.timestamp_us)?
+                )),
                 TimeFormat::Ctime => {
-                    print!("[{}] ", time_formatter::ctime(record.timestamp_us))
+                    Some(format!("[{}]", time_formatter::ctime(record.timestamp_us)?))
                 }
-                TimeFormat::Iso => {
-                    print!("{} ", time_formatter::iso(record.timestamp_us))
-                }
-                TimeFormat::Raw => {
-                    print!("[{}] ", time_formatter::raw(record.timestamp_us))
-                }
-                TimeFormat::Notime => (),
+                TimeFormat::Iso => Some(time_formatter::iso(record.timestamp_us)?),
+                TimeFormat::Raw => Some(format!("[{}]", time_formatter::raw(record.timestamp_us))),
+                TimeFormat::Notime => None,
+            };
+            if let Some(timestamp) = timestamp {
+                let color = match self.time_format {
+                    TimeFormat::Reltime if reltime_formatter.is_time_break() => "timebreak",
+                    _ => "time",
+                };
+                print!("{} ", self.paint(color, &timestamp));
             }
-            println!("{}", record.message);
+            println!("{}", self.paint_message(&record));
         }
         Ok(())
     }
 
+    fn paint(&self, color: &str, text: &str) -> String {
+        match &self.colors {
+            Some(colors) => colors.paint(color, text),
+            None => text.to_string(),
+        }
+    }
+
+    /// Highlights the subsystem prefix of the message, and the rest according to its level.
+    fn paint_message(&self, record: &Record) -> String {
+        let Some(colors) = &self.colors else {
+            return record.message.clone();
+        };
+        // Like util-linux, the prefix goes up to the first colon followed by a blank
+        let (subsys, text) = record
+            .message
+            .match_indices(':')
+            .map(|(i, _)| i + 1)
+            .find(|&i| record.message[i..].starts_with([' ', '\t']))
+            .map_or(("", record.message.as_str()), |i| {
+                record.message.split_at(i + 1)
+            });
+        let color = match Level::from(record.priority_facility) {
+            Level::Emerg | Level::Alert => "alert",
+            Level::Crit => "crit",
+            Level::Err => "err",
+            Level::Warn => "warn",
+            _ => "",
+        };
+        colors.paint("subsys", subsys) + &colors.paint(color, text)
+    }
+
     fn try_filtered_iter(&self) -> UResult<impl Iterator<Item = UResult<Record>> + '_> {
-        Ok(self
+        let records = self
             .try_iter()?
             .filter(Self::is_record_in_set(&self.facility_filters))
             .filter(Self::is_record_in_set(&self.level_filters))
-            .filter(Self::is_record_since(&self.since_filter))
-            .filter(Self::is_record_until(&self.until_filter)))
+            .filter(Self::is_record_in_time_range(&self.time_range))
+            .filter(Self::is_record_in_sequence_range(&self.sequence_range))
+            .filter(Self::is_record_with_value(
+                "SUBSYSTEM",
+                &self.subsystem_filters,
+            ))
+            .filter(Self::is_record_with_value("DEVICE", &self.device_filters));
+        // Last, so that the context records went through the other filters
+        Ok(self.message_filter.filter(records))
     }
 
     fn try_iter(&self) -> UResult<RecordIterator> {
+        if self.syslog {
+            return self.try_syslog_iter();
+        }
+        match self.try_kmsg_iter() {
+            // Old kernels and restricted environments only provide syslog(2)
+            Err(e) if self.kmsg_file == KMSG_DEVICE && !self.follow => {
+                self.try_syslog_iter().map_err(|_| e)
+            }
+            result => result,
+        }
+    }
+
+    fn try_syslog_iter(&self) -> UResult<RecordIterator> {
+        let size = match self.buffer_size {
+            Some(size) => size,
+            None => syslog::buffer_size().map_err_context(|| "klogctl failed".to_string())?,
+        };
+        let buf = syslog::read_all(size, self.read_clear)
+            .map_err_context(|| "klogctl failed".to_string())?;
+        Ok(RecordIterator {
+            file_reader: Box::new(Cursor::new(buf)),
+            kmsg_record_separator: Some(b'\n'),
+            record_format: RecordFormat::Syslog,
+        })
+    }
+
+    fn try_kmsg_iter(&self) -> UResult<RecordIterator> {
         let mut open_option = OpenOptions::new();
         open_option.read(true);
 
+        // In follow mode, reads block until the kernel logs a new record
         #[cfg(not(target_os = "windows"))]
-        open_option.custom_flags(libc::O_NONBLOCK);
+        if !self.follow {
+            open_option.custom_flags(libc::O_NONBLOCK);
+        }
 
         let file = open_option
             .open(self.kmsg_file)
@@ -281,14 +751,22 @@ impl Dmesg<'_> {
 
         #[cfg(not(target_os = "windows"))]
         {
+            // For /dev/kmsg, SEEK_DATA goes to the first record still in the buffer, and
+            // SEEK_END right after the last one. Both fail harmlessly on a FIFO.
+            let whence = if self.follow_new {
+                libc::SEEK_END
+            } else {
+                libc::SEEK_DATA
+            };
             let fd = file.as_raw_fd();
-            unsafe { libc::lseek(fd, 0, libc::SEEK_DATA) };
+            unsafe { libc::lseek(fd, 0, whence) };
         }
 
         let file_reader = BufReader::new(file);
         Ok(RecordIterator {
-            file_reader,
+            file_reader: Box::new(file_reader),
             kmsg_record_separator: self.kmsg_record_separator,
+            record_format: RecordFormat::Kmsg,
         })
     }
 
@@ -304,28 +782,32 @@ impl Dmesg<'_> {
         }
     }
 
-    fn is_record_since(
-        since: &Option<DateTime<FixedOffset>>,
-    ) -> impl Fn(&UResult<Record>) -> bool + '_ {
-        move |record: &UResult<Record>| match (record, since) {
-            (Ok(record), Some(since)) => {
-                let time =
-                    time_formatter::datetime_from_microseconds_since_boot(record.timestamp_us);
-                time >= *since
-            }
+    fn is_record_with_value<'a>(
+        key: &'a str,
+        values: &'a Option<HashSet<String>>,
+    ) -> impl Fn(&UResult<Record>) -> bool + 'a {
+        move |record: &UResult<Record>| match (record, values) {
+            (Ok(record), Some(values)) => record
+                .dictionary_value(key)
+                .is_some_and(|value| values.contains(value)),
+            _ => true,
+        }
+    }
+
+    fn is_record_in_time_range(range: &range::TimeRange) -> impl Fn(&UResult<Record>) -> bool + '_ {
+        move |record: &UResult<Record>| match record {
+            Ok(record) => range.contains(&record.timestamp_us),
             _ => true,
         }
     }
 
-    fn is_record_until(
-        until: &Option<DateTime<FixedOffset>>,
+    fn is_record_in_sequence_range(
+        range: &range::Range<u64>,
     ) -> impl Fn(&UResult<Record>) -> bool + '_ {
-        move |record: &UResult<Record>| match (record, until) {
-            (Ok(record), Some(until)) => {
-                let time =
-                    time_formatter::datetime_from_microseconds_since_boot(record.timestamp_us);
-                time <= *until
-            }
+        move |record: &UResult<Record>| match record {
+            Ok(record) if !range.is_unbounded() => record
+                .sequence
+                .is_some_and(|sequence| range.contains(&sequence)),
             _ => true,
         }
     }
@@ -334,6 +816,9 @@ impl Dmesg<'_> {
 enum OutputFormat {
     Normal,
     Json,
+    Ndjson,
+    Logfmt,
+    Rfc5424,
 }
 
 enum TimeFormat {
@@ -345,7 +830,7 @@ enum TimeFormat {
     Raw,
 }
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 enum Facility {
     Kern,
     User,
@@ -374,7 +859,7 @@ enum Facility {
     Unknown,
 }
 
-#[derive(Eq, Hash, PartialEq)]
+#[derive(Clone, Copy, Eq, Hash, PartialEq)]
 enum Level {
     Emerg,
     Alert,
@@ -388,8 +873,16 @@ enum Level {
 }
 
 struct RecordIterator {
-    file_reader: BufReader<File>,
-    kmsg_record_separator: u8,
+    file_reader: Box<dyn BufRead>,
+    kmsg_record_separator: Option<u8>,
+    record_format: RecordFormat,
+}
+
+enum RecordFormat {
+    /// `pri,seq,time,flags;msg` records, from `/dev/kmsg`
+    Kmsg,
+    /// `<pri>[timestamp] msg` lines, from syslog(2)
+    Syslog,
 }
 
 impl Iterator for RecordIterator {
@@ -410,24 +903,44 @@ impl Iterator for RecordIterator {
 impl RecordIterator {
     fn read_record_line(&mut self) -> UResult<Option<String>> {
         let mut buf = vec![];
-        match self
-            .file_reader
-            .read_until(self.kmsg_record_separator, &mut buf)
-        {
+        let result = match self.kmsg_record_separator {
+            Some(separator) => self.file_reader.read_until(separator, &mut buf),
+            // The record comes with its dictionary, on continuation lines. The buffer is larger
+            // than the one of the BufReader, which is never filled, so this is a single read(2).
+            None => {
+                buf.resize(KMSG_RECORD_MAX, 0);
+                let result = self.file_reader.read(&mut buf);
+                buf.truncate(*result.as_ref().unwrap_or(&0));
+                result
+            }
+        };
+        match result {
             /*
              * - a read(2) from /dev/kmsg returns WouldBlock if there aren't
              *   any new record
-             * - a read(2) from a file returns 0 if the we reached the end
+             * - a read(2) from a file returns 0 if the we reached the end,
+             *   and so does a FIFO once its writer is gone
              * In these cases return Ok(None)
              */
             Ok(0) => Ok(None),
             Ok(_) => Ok(Some(String::from_utf8_lossy(&buf).to_string())),
             Err(e) if e.kind() == ErrorKind::WouldBlock => Ok(None),
+            // The record was overwritten in the ring buffer before we could read it, the next
+            // read(2) returns the oldest record still available
+            #[cfg(not(target_os = "windows"))]
+            Err(e) if e.raw_os_error() == Some(libc::EPIPE) => self.read_record_line(),
             Err(e) => Err(Box::new(UIoError::from(e))),
         }
     }
 
     fn parse_record(&self, record_line: &str) -> Option<Record> {
+        match self.record_format {
+            RecordFormat::Kmsg => Self::parse_kmsg_record(record_line),
+            RecordFormat::Syslog => Self::parse_syslog_record(record_line),
+        }
+    }
+
+    fn parse_kmsg_record(record_line: &str) -> Option<Record> {
         record_regex()
             .captures_iter(record_line)
             .map(|c| c.extract())
@@ -435,9 +948,61 @@ impl RecordIterator {
                 Record::from_str_fields(pri_fac, seq, time, msg.to_string()).ok()
             })
             .next()
+            .map(|mut record| {
+                record.dictionary = Self::parse_dictionary(record_line);
+                record
+            })
+    }
+
+    // The lines after the first one are ` KEY=value` pairs, such as SUBSYSTEM and DEVICE
+    fn parse_dictionary(record_line: &str) -> Vec<(String, String)> {
+        record_line
+            .lines()
+            .skip(1)
+            .filter_map(|line| line.strip_prefix(' ')?.split_once('='))
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
     }
+
+    fn parse_syslog_record(record_line: &str) -> Option<Record> {
+        let line = record_line.trim_end_matches('\n');
+        if line.is_empty() {
+            return None;
+        }
+        let captures = syslog_record_regex().captures(line)?;
+        // Lines without a priority are logged at the kernel's default level, KERN_WARNING
+        let priority_facility = match captures.get(1) {
+            Some(pri) => pri.as_str().parse().ok()?,
+            None => 4,
+        };
+        // Timestamps are missing when printk.time is disabled
+        let timestamp_us = match (captures.get(2), captures.get(3)) {
+            (Some(seconds), Some(sub_seconds)) => {
+                let seconds: i64 = seconds.as_str().parse().ok()?;
+                let sub_seconds = format!("{:0<6.6}", sub_seconds.as_str());
+                seconds * 1000000 + sub_seconds.parse::<i64>().ok()?
+            }
+            _ => 0,
+        };
+        Some(Record {
+            priority_facility,
+            // syslog(2) doesn't tell the sequence numbers of the records
+            sequence: None,
+            timestamp_us,
+            message: captures[4].to_string(),
+            dictionary: Vec::new(),
+        })
+    }
+}
+
+fn syslog_record_regex() -> &'static Regex {
+    SYSLOG_RECORD_REGEX.get_or_init(|| {
+        Regex::new(r"^(?:<([0-9]+)>)?(?:\[ *([0-9]+)\.([0-9]+)\] ?)?(.*)$").expect("invalid regex.")
+    })
 }
 
+static SYSLOG_RECORD_REGEX: OnceLock<Regex> = OnceLock::new();
+
 fn record_regex() -> &'static Regex {
     RECORD_REGEX.get_or_init(|| {
         let valid_number_pattern = "0|[1-9][0-9]*";
@@ -454,9 +1019,10 @@ static RECORD_REGEX: OnceLock<Regex> = OnceLock::new();
 
 struct Record {
     priority_facility: u32,
-    _sequence: u64,
+    sequence: Option<u64>,
     timestamp_us: i64,
     message: String,
+    dictionary: Vec<(String, String)>,
 }
 
 impl Record {
@@ -467,13 +1033,21 @@ impl Record {
         match (pri_fac, seq, time) {
             (Ok(pri_fac), Ok(seq), Ok(time)) => Ok(Record {
                 priority_facility: pri_fac,
-                _sequence: seq,
+                sequence: Some(seq),
                 timestamp_us: time,
                 message: msg,
+                dictionary: Vec::new(),
             }),
             _ => Err(USimpleError::new(1, "Failed to parse record field(s)")),
         }
     }
+
+    fn dictionary_value(&self, key: &str) -> Option<&str> {
+        self.dictionary
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, value)| value.as_str())
+    }
 }
 
 impl From<u32> for Level {
@@ -493,6 +1067,79 @@ impl From<u32> for Level {
     }
 }
 
+impl FromStr for Level {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, ()> {
+        (0..8)
+            .map(Level::from)
+            .find(|level| level.name() == s)
+            .ok_or(())
+    }
+}
+
+impl Level {
+    fn name(&self) -> &'static str {
+        match self {
+            Level::Emerg => "emerg",
+            Level::Alert => "alert",
+            Level::Crit => "crit",
+            Level::Err => "err",
+            Level::Warn => "warn",
+            Level::Notice => "notice",
+            Level::Info => "info",
+            Level::Debug => "debug",
+            Level::Unknown => "",
+        }
+    }
+}
+
+impl FromStr for Facility {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Facility::all()
+            .find(|facility| facility.name() == s)
+            .ok_or(())
+    }
+}
+
+impl Facility {
+    fn all() -> impl Iterator<Item = Facility> {
+        (0..24).map(|facility| Facility::from(facility << 3))
+    }
+
+    fn name(&self) -> &'static str {
+        match self {
+            Facility::Kern => "kern",
+            Facility::User => "user",
+            Facility::Mail => "mail",
+            Facility::Daemon => "daemon",
+            Facility::Auth => "auth",
+            Facility::Syslog => "syslog",
+            Facility::Lpr => "lpr",
+            Facility::News => "news",
+            Facility::Uucp => "uucp",
+            Facility::Cron => "cron",
+            Facility::Authpriv => "authpriv",
+            Facility::Ftp => "ftp",
+            Facility::Res0 => "res0",
+            Facility::Res1 => "res1",
+            Facility::Res2 => "res2",
+            Facility::Res3 => "res3",
+            Facility::Local0 => "local0",
+            Facility::Local1 => "local1",
+            Facility::Local2 => "local2",
+            Facility::Local3 => "local3",
+            Facility::Local4 => "local4",
+            Facility::Local5 => "local5",
+            Facility::Local6 => "local6",
+            Facility::Local7 => "local7",
+            Facility::Unknown => "",
+        }
+    }
+}
+
 impl From<u32> for Facility {
     fn from(value: u32) -> Self {
         let facility = (value >> 3) as u8;
@@ -525,3 +1172,34 @@ impl From<u32> for Facility {
         }
     }
 }
+
+#[test]
+fn test_parse_console_level() {
+    assert_eq!(parse_console_level("1").unwrap(), 1);
+    assert_eq!(parse_console_level("emerg").unwrap(), 1);
+    assert_eq!(parse_console_level("warn").unwrap(), 5);
+    assert_eq!(parse_console_level("debug").unwrap(), 8);
+    assert!(parse_console_level("0").is_err());
+    assert!(parse_console_level("9").is_err());
+    assert!(parse_console_level("definitely-invalid").is_err());
+}
+
+#[test]
+fn test_parse_syslog_record() {
+    let record =
+        RecordIterator::parse_syslog_record("<6>[   12.345678] usb 1-1: new device\n").unwrap();
+    assert_eq!(record.priority_facility, 6);
+    assert_eq!(record.timestamp_us, 12345678);
+    assert_eq!(record.message, "usb 1-1: new device");
+
+    let record = RecordIterator::parse_syslog_record("<14>[0.5] from userspace").unwrap();
+    assert_eq!(record.priority_facility, 14);
+    assert_eq!(record.timestamp_us, 500000);
+
+    let record = RecordIterator::parse_syslog_record("no prefix at all").unwrap();
+    assert_eq!(record.priority_facility, 4);
+    assert_eq!(record.timestamp_us, 0);
+    assert_eq!(record.message, "no prefix at all");
+
+    assert!(RecordIterator::parse_syslog_record("\n").is_none());
+}
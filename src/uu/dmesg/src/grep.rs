@@ -0,0 +1,123 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Filtering of the records by message, with context records around the matches like grep(1).
+
+use crate::Record;
+use regex::{Regex, RegexBuilder};
+use std::collections::VecDeque;
+use uucore::error::{UResult, USimpleError};
+
+#[derive(Default)]
+pub struct MessageFilter {
+    /// A record matches if its message matches any of them, or if there is none
+    patterns: Vec<Regex>,
+    /// Records whose message matches any of them are dropped, even as context
+    excludes: Vec<Regex>,
+    /// Number of records to keep before each match
+    before: usize,
+    /// Number of records to keep after each match
+    after: usize,
+}
+
+impl MessageFilter {
+    pub fn new<'a>(
+        patterns: impl IntoIterator<Item = &'a String>,
+        excludes: impl IntoIterator<Item = &'a String>,
+        ignore_case: bool,
+        before: usize,
+        after: usize,
+    ) -> UResult<Self> {
+        let compile = |pattern: &String| {
+            RegexBuilder::new(pattern)
+                .case_insensitive(ignore_case)
+                .build()
+                .map_err(|_| USimpleError::new(1, format!("invalid pattern '{pattern}'")))
+        };
+        Ok(Self {
+            patterns: patterns.into_iter().map(compile).collect::<UResult<_>>()?,
+            excludes: excludes.into_iter().map(compile).collect::<UResult<_>>()?,
+            before,
+            after,
+        })
+    }
+
+    fn is_excluded(&self, record: &Record) -> bool {
+        self.excludes
+            .iter()
+            .any(|exclude| exclude.is_match(&record.message))
+    }
+
+    fn is_match(&self, record: &Record) -> bool {
+        self.patterns.is_empty()
+            || self
+                .patterns
+                .iter()
+                .any(|pattern| pattern.is_match(&record.message))
+    }
+
+    pub fn filter<I>(&self, records: I) -> Matches<'_, I>
+    where
+        I: Iterator<Item = UResult<Record>>,
+    {
+        Matches {
+            filter: self,
+            records,
+            before: VecDeque::new(),
+            after: 0,
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+/// The records that match, along with the records around them kept as context.
+pub(crate) struct Matches<'a, I> {
+    filter: &'a MessageFilter,
+    records: I,
+    /// The last records that didn't match, in case the next one does
+    before: VecDeque<Record>,
+    /// Number of records still to keep after the last match
+    after: usize,
+    /// A match and the records before it
+    pending: VecDeque<Record>,
+}
+
+impl<I> Iterator for Matches<'_, I>
+where
+    I: Iterator<Item = UResult<Record>>,
+{
+    type Item = UResult<Record>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(record) = self.pending.pop_front() {
+            return Some(Ok(record));
+        }
+        loop {
+            let record = match self.records.next()? {
+                Ok(record) => record,
+                error => return Some(error),
+            };
+            if self.filter.is_excluded(&record) {
+                continue;
+            }
+            if self.filter.is_match(&record) {
+                self.after = self.filter.after;
+                self.pending.append(&mut self.before);
+                self.pending.push_back(record);
+                return self.pending.pop_front().map(Ok);
+            }
+            if self.after > 0 {
+                self.after -= 1;
+                return Some(Ok(record));
+            }
+            if self.filter.before > 0 {
+                if self.before.len() == self.filter.before {
+                    self.before.pop_front();
+                }
+                self.before.push_back(record);
+            }
+        }
+    }
+}
@@ -6,8 +6,9 @@
 use serde::Serialize;
 use std::io;
 
-pub fn serialize_records(records: &Vec<crate::Record>) -> String {
-    let json = Dmesg::from(records);
+/// With `decode`, the facility and level are given by name rather than as a priority number.
+pub fn serialize_records(records: &[crate::Record], decode: bool) -> String {
+    let json = Dmesg::new(records, decode);
     let formatter = DmesgFormatter::new();
     let mut buf = vec![];
     let mut serializer = serde_json::Serializer::with_formatter(&mut buf, formatter);
@@ -15,6 +16,16 @@ pub fn serialize_records(records: &Vec<crate::Record>) -> String {
     String::from_utf8_lossy(&buf).to_string()
 }
 
+/// A single record as a JSON object on one line, for newline-delimited JSON.
+pub fn serialize_record(record: &crate::Record, decode: bool) -> String {
+    let mut buf = vec![];
+    let mut serializer = serde_json::Serializer::with_formatter(&mut buf, NdjsonFormatter);
+    Record::new(record, decode)
+        .serialize(&mut serializer)
+        .unwrap();
+    String::from_utf8_lossy(&buf).to_string()
+}
+
 #[derive(serde::Serialize)]
 struct Dmesg<'a> {
     dmesg: Vec<Record<'a>>,
@@ -22,23 +33,50 @@ struct Dmesg<'a> {
 
 #[derive(serde::Serialize)]
 struct Record<'a> {
-    pri: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    fac: Option<&'static str>,
+    pri: serde_json::Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seq: Option<u64>,
     time: i64,
     msg: &'a str,
+    #[serde(skip_serializing_if = "serde_json::Map::is_empty")]
+    dict: serde_json::Map<String, serde_json::Value>,
 }
 
-impl<'a> From<&'a Vec<crate::Record>> for Dmesg<'a> {
-    fn from(value: &'a Vec<crate::Record>) -> Self {
-        let mut dmesg_json = Dmesg { dmesg: vec![] };
-        for record in value {
-            let record_json = Record {
-                pri: record.priority_facility,
-                time: record.timestamp_us,
-                msg: &record.message,
-            };
-            dmesg_json.dmesg.push(record_json);
+impl<'a> Dmesg<'a> {
+    fn new(records: &'a [crate::Record], decode: bool) -> Self {
+        Dmesg {
+            dmesg: records
+                .iter()
+                .map(|record| Record::new(record, decode))
+                .collect(),
+        }
+    }
+}
+
+impl<'a> Record<'a> {
+    fn new(record: &'a crate::Record, decode: bool) -> Self {
+        let (fac, pri) = if decode {
+            (
+                Some(crate::Facility::from(record.priority_facility).name()),
+                crate::Level::from(record.priority_facility).name().into(),
+            )
+        } else {
+            (None, record.priority_facility.into())
+        };
+        Record {
+            fac,
+            pri,
+            seq: record.sequence,
+            time: record.timestamp_us,
+            msg: &record.message,
+            dict: record
+                .dictionary
+                .iter()
+                .map(|(key, value)| (key.clone(), value.as_str().into()))
+                .collect(),
         }
-        dmesg_json
     }
 }
 
@@ -140,3 +178,15 @@ impl serde_json::ser::Formatter for DmesgFormatter {
         writer.write_all(repr.as_bytes())
     }
 }
+
+/// Compact, except for the time which is in seconds like with [`DmesgFormatter`].
+struct NdjsonFormatter;
+
+impl serde_json::ser::Formatter for NdjsonFormatter {
+    fn write_i64<W>(&mut self, writer: &mut W, value: i64) -> io::Result<()>
+    where
+        W: ?Sized + io::Write,
+    {
+        writer.write_all(crate::time_formatter::seconds(value).as_bytes())
+    }
+}
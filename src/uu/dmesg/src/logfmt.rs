@@ -0,0 +1,76 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Records as `key=value` pairs, with the same fields as the JSON output.
+
+use crate::{time_formatter, Facility, Level, Record};
+
+/// With `decode`, the facility and level are given by name rather than as a priority number.
+pub fn format_record(record: &Record, decode: bool) -> String {
+    let mut pairs = vec![];
+    if decode {
+        pairs.push((
+            "fac",
+            Facility::from(record.priority_facility).name().to_string(),
+        ));
+        pairs.push((
+            "pri",
+            Level::from(record.priority_facility).name().to_string(),
+        ));
+    } else {
+        pairs.push(("pri", record.priority_facility.to_string()));
+    }
+    if let Some(sequence) = record.sequence {
+        pairs.push(("seq", sequence.to_string()));
+    }
+    pairs.push(("time", time_formatter::seconds(record.timestamp_us)));
+    pairs.push(("msg", record.message.clone()));
+    pairs.extend(
+        record
+            .dictionary
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone())),
+    );
+    pairs
+        .iter()
+        .map(|(key, value)| format!("{key}={}", quote(value)))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Values with blanks, quotes or `=` are quoted.
+fn quote(value: &str) -> String {
+    if !value.is_empty()
+        && !value
+            .chars()
+            .any(|c| c.is_whitespace() || c.is_control() || matches!(c, '"' | '=' | '\\'))
+    {
+        return value.to_string();
+    }
+    let mut quoted = String::from('"');
+    for c in value.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+#[test]
+fn test_quote() {
+    assert_eq!(quote("usb"), "usb");
+    assert_eq!(quote("+usb:1-1"), "+usb:1-1");
+    assert_eq!(quote(""), "\"\"");
+    assert_eq!(quote("new device"), "\"new device\"");
+    assert_eq!(quote("a=b"), "\"a=b\"");
+    assert_eq!(quote("say \"hi\"\n"), "\"say \\\"hi\\\"\\n\"");
+    assert_eq!(quote("C:\\"), "\"C:\\\\\"");
+}
@@ -0,0 +1,57 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::{
+    env,
+    io::{self, IsTerminal, Write},
+    os::fd::AsRawFd,
+    process::{Child, Command, Stdio},
+};
+use uucore::libc;
+
+/// Redirects stdout to `$PAGER`, or `less`, for as long as it lives.
+pub struct Pager {
+    child: Child,
+}
+
+impl Pager {
+    /// Returns `None` if stdout isn't a terminal or the pager can't be started, in which case
+    /// the output goes to stdout as usual.
+    pub fn start() -> Option<Self> {
+        if !io::stdout().is_terminal() {
+            return None;
+        }
+        let pager = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        if pager.is_empty() || pager == "cat" {
+            return None;
+        }
+
+        let mut command = Command::new("sh");
+        command.arg("-c").arg(&pager).stdin(Stdio::piped());
+        // Same defaults as util-linux, so that less passes the colors through and exits if the
+        // output fits on one screen
+        if env::var_os("LESS").is_none() {
+            command.env("LESS", "FRSX");
+        }
+        let mut child = command.spawn().ok()?;
+
+        let stdin = child.stdin.take()?;
+        io::stdout().flush().ok()?;
+        // The pipe stays open through stdout once `stdin` is dropped
+        if unsafe { libc::dup2(stdin.as_raw_fd(), libc::STDOUT_FILENO) } < 0 {
+            return None;
+        }
+        Some(Self { child })
+    }
+}
+
+impl Drop for Pager {
+    fn drop(&mut self) {
+        let _ = io::stdout().flush();
+        // The pager only exits once it reads the end of its input
+        unsafe { libc::close(libc::STDOUT_FILENO) };
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,141 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Ranges of records selected by `--since`/`--until` and `--since-seq`/`--until-seq`.
+//!
+//! Besides absolute dates, times can be relative to now, as in `-10min`, `+1h` or `5s ago`, or
+//! relative to boot, as in `boot+30s`. A duration is a list of numbers, each followed by a unit
+//! such as `us`, `ms`, `s`, `min`, `h`, `d` or `w`, and defaulting to seconds.
+
+use crate::time_formatter;
+use uucore::error::{UResult, USimpleError};
+
+/// Both bounds are inclusive, and a missing bound doesn't restrict the range.
+pub struct Range<T> {
+    since: Option<T>,
+    until: Option<T>,
+}
+
+/// Times as microseconds since boot, like the timestamps of the records.
+pub type TimeRange = Range<i64>;
+
+impl<T: PartialOrd> Range<T> {
+    pub fn new(since: Option<T>, until: Option<T>) -> Self {
+        Self { since, until }
+    }
+
+    pub fn is_unbounded(&self) -> bool {
+        self.since.is_none() && self.until.is_none()
+    }
+
+    pub fn contains(&self, value: &T) -> bool {
+        !matches!(&self.since, Some(since) if value < since)
+            && !matches!(&self.until, Some(until) if value > until)
+    }
+}
+
+impl TimeRange {
+    pub fn parse(since: Option<&str>, until: Option<&str>) -> UResult<Self> {
+        Ok(Self::new(
+            since.map(parse_time).transpose()?,
+            until.map(parse_time).transpose()?,
+        ))
+    }
+}
+
+/// Parses an absolute or relative time into microseconds since boot.
+pub fn parse_time(s: &str) -> UResult<i64> {
+    let invalid = || USimpleError::new(1, format!("invalid time value \"{s}\""));
+    let s = s.trim();
+
+    if let Some(offset) = s.strip_prefix("boot") {
+        let offset = offset.trim_start();
+        return match offset.chars().next() {
+            None => Ok(0),
+            Some('+') => parse_duration(&offset[1..]).ok_or_else(invalid),
+            Some('-') => parse_duration(&offset[1..])
+                .map(|duration| -duration)
+                .ok_or_else(invalid),
+            _ => Err(invalid()),
+        };
+    }
+
+    let now = time_formatter::microseconds_since_boot(chrono::Local::now().into())?;
+    let relative = if let Some(duration) = s.strip_prefix('+') {
+        parse_duration(duration)
+    } else if let Some(duration) = s.strip_prefix('-') {
+        parse_duration(duration).map(|duration| -duration)
+    } else if let Some(duration) = s.strip_suffix("ago") {
+        parse_duration(duration).map(|duration| -duration)
+    } else if s == "now" {
+        Some(0)
+    } else {
+        None
+    };
+    match relative {
+        Some(offset) => Ok(now.saturating_add(offset)),
+        None => match time_formatter::parse_datetime(s) {
+            Ok(date_time) => time_formatter::microseconds_since_boot(date_time),
+            Err(_) => Err(invalid()),
+        },
+    }
+}
+
+/// A duration such as `1h 30min` in microseconds.
+fn parse_duration(s: &str) -> Option<i64> {
+    let mut rest = s.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total: i64 = 0;
+    while !rest.is_empty() {
+        let digits = rest
+            .find(|c: char| !c.is_ascii_digit())
+            .unwrap_or(rest.len());
+        let value: i64 = rest[..digits].parse().ok()?;
+        rest = rest[digits..].trim_start();
+        let letters = rest
+            .find(|c: char| !c.is_ascii_alphabetic())
+            .unwrap_or(rest.len());
+        let unit: i64 = match &rest[..letters] {
+            "us" | "usec" => 1,
+            "ms" | "msec" => 1_000,
+            "" | "s" | "sec" | "second" | "seconds" => 1_000_000,
+            "m" | "min" | "minute" | "minutes" => 60_000_000,
+            "h" | "hr" | "hour" | "hours" => 3_600_000_000,
+            "d" | "day" | "days" => 86_400_000_000,
+            "w" | "week" | "weeks" => 604_800_000_000,
+            _ => return None,
+        };
+        total = total.checked_add(value.checked_mul(unit)?)?;
+        rest = rest[letters..].trim_start();
+    }
+    Some(total)
+}
+
+#[test]
+fn test_parse_duration() {
+    assert_eq!(parse_duration("30s"), Some(30_000_000));
+    assert_eq!(parse_duration("30"), Some(30_000_000));
+    assert_eq!(parse_duration("10min"), Some(600_000_000));
+    assert_eq!(parse_duration(" 1h 30min "), Some(5_400_000_000));
+    assert_eq!(parse_duration("1d2h"), Some(93_600_000_000));
+    assert_eq!(parse_duration("250ms"), Some(250_000));
+    assert_eq!(parse_duration(""), None);
+    assert_eq!(parse_duration("min"), None);
+    assert_eq!(parse_duration("10 parsecs"), None);
+    assert_eq!(parse_duration("99999999999999999w"), None);
+}
+
+#[test]
+fn test_range_contains() {
+    let range = Range::new(Some(2), Some(4));
+    assert!(!range.contains(&1));
+    assert!(range.contains(&2));
+    assert!(range.contains(&4));
+    assert!(!range.contains(&5));
+    assert!(Range::new(None, Some(4)).contains(&i64::MIN));
+    assert!(Range::<i64>::new(None, None).is_unbounded());
+}
@@ -0,0 +1,55 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Records as RFC 5424 syslog messages, for forwarding them to a log collector.
+
+use crate::{time_formatter, Facility, Record};
+use std::sync::OnceLock;
+use uucore::error::UResult;
+
+/// The value of a header field that isn't known.
+const NIL: &str = "-";
+
+/// The priority keeps the original facility and severity of the record.
+pub fn format_record(record: &Record) -> UResult<String> {
+    let app_name = match Facility::from(record.priority_facility) {
+        Facility::Kern => "kernel",
+        _ => NIL,
+    };
+    // Version 1, without process ID, message ID or structured data
+    Ok(format!(
+        "<{}>1 {} {} {app_name} {NIL} {NIL} {NIL} {}",
+        record.priority_facility,
+        time_formatter::rfc3339(record.timestamp_us)?,
+        hostname(),
+        record.message
+    ))
+}
+
+fn hostname() -> &'static str {
+    static HOSTNAME: OnceLock<String> = OnceLock::new();
+    HOSTNAME.get_or_init(|| {
+        read_hostname()
+            .filter(|name| !name.is_empty() && name.chars().all(|c| c.is_ascii_graphic()))
+            .unwrap_or_else(|| NIL.to_string())
+    })
+}
+
+#[cfg(unix)]
+fn read_hostname() -> Option<String> {
+    use uucore::libc;
+
+    let mut buf = [0u8; 256];
+    if unsafe { libc::gethostname(buf.as_mut_ptr() as *mut libc::c_char, buf.len()) } != 0 {
+        return None;
+    }
+    let len = buf.iter().position(|&b| b == 0).unwrap_or(buf.len());
+    String::from_utf8(buf[..len].to_vec()).ok()
+}
+
+#[cfg(not(unix))]
+fn read_hostname() -> Option<String> {
+    std::env::var("COMPUTERNAME").ok()
+}
@@ -0,0 +1,95 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Access to the kernel ring buffer through syslog(2), for kernels and environments without
+//! `/dev/kmsg`.
+
+use std::io;
+
+// Actions from syslog(2)
+const SYSLOG_ACTION_READ_ALL: i32 = 3;
+const SYSLOG_ACTION_READ_CLEAR: i32 = 4;
+const SYSLOG_ACTION_CLEAR: i32 = 5;
+const SYSLOG_ACTION_CONSOLE_OFF: i32 = 6;
+const SYSLOG_ACTION_CONSOLE_ON: i32 = 7;
+const SYSLOG_ACTION_CONSOLE_LEVEL: i32 = 8;
+const SYSLOG_ACTION_SIZE_BUFFER: i32 = 10;
+
+/// Reads into `buf` with one of the read actions, and returns the number of bytes read.
+#[cfg(target_os = "linux")]
+fn klogctl_read(action: i32, buf: &mut [u8]) -> io::Result<usize> {
+    use uucore::libc;
+
+    let len = buf.len().min(libc::c_int::MAX as usize) as libc::c_int;
+    // SAFETY: the kernel writes at most `len` bytes, and `buf` is valid for that many
+    let ret = unsafe { libc::klogctl(action, buf.as_mut_ptr() as *mut libc::c_char, len) };
+    klogctl_result(ret)
+}
+
+/// Runs one of the actions that don't read anything, some of which take an argument.
+#[cfg(target_os = "linux")]
+fn klogctl_arg(action: i32, arg: i32) -> io::Result<usize> {
+    use uucore::libc;
+
+    // SAFETY: these actions don't access the buffer, so a null one is fine
+    let ret = unsafe { libc::klogctl(action, std::ptr::null_mut(), arg) };
+    klogctl_result(ret)
+}
+
+#[cfg(target_os = "linux")]
+fn klogctl_result(ret: i32) -> io::Result<usize> {
+    if ret < 0 {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(ret as usize)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn klogctl_read(_action: i32, _buf: &mut [u8]) -> io::Result<usize> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn klogctl_arg(_action: i32, _arg: i32) -> io::Result<usize> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// Size of the kernel ring buffer.
+pub fn buffer_size() -> io::Result<usize> {
+    klogctl_arg(SYSLOG_ACTION_SIZE_BUFFER, 0)
+}
+
+/// Reads up to `size` bytes of the most recent messages, and clears the buffer if `clear` is set.
+pub fn read_all(size: usize, clear: bool) -> io::Result<Vec<u8>> {
+    let action = if clear {
+        SYSLOG_ACTION_READ_CLEAR
+    } else {
+        SYSLOG_ACTION_READ_ALL
+    };
+    let mut buf = vec![0; size];
+    let len = klogctl_read(action, &mut buf)?;
+    buf.truncate(len);
+    Ok(buf)
+}
+
+pub fn clear() -> io::Result<()> {
+    klogctl_arg(SYSLOG_ACTION_CLEAR, 0).map(|_| ())
+}
+
+/// Stops printing messages to the console, except emergency ones.
+pub fn console_off() -> io::Result<()> {
+    klogctl_arg(SYSLOG_ACTION_CONSOLE_OFF, 0).map(|_| ())
+}
+
+/// Restores the console level that was in effect before [`console_off`].
+pub fn console_on() -> io::Result<()> {
+    klogctl_arg(SYSLOG_ACTION_CONSOLE_ON, 0).map(|_| ())
+}
+
+/// Only messages more severe than `level` (1 to 8) are printed to the console.
+pub fn set_console_level(level: u32) -> io::Result<()> {
+    klogctl_arg(SYSLOG_ACTION_CONSOLE_LEVEL, level as i32).map(|_| ())
+}
@@ -3,6 +3,9 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+use chrono::Utc;
 use chrono::{DateTime, FixedOffset, TimeDelta};
 #[cfg(feature = "fixed-boot-time")]
 use chrono::{NaiveDate, NaiveTime};
@@ -15,24 +18,38 @@ pub fn raw(timestamp_us: i64) -> String {
     format!("{:>5}.{:0>6}", seconds, sub_seconds)
 }
 
-pub fn ctime(timestamp_us: i64) -> String {
-    let date_time = boot_time()
-        .checked_add_signed(TimeDelta::microseconds(timestamp_us))
-        .unwrap();
-    date_time.format("%a %b %d %H:%M:%S %Y").to_string()
+/// Seconds with microseconds, without padding.
+pub fn seconds(timestamp_us: i64) -> String {
+    let sign = if timestamp_us < 0 { "-" } else { "" };
+    let timestamp_us = timestamp_us.unsigned_abs();
+    format!(
+        "{sign}{}.{:06}",
+        timestamp_us / 1000000,
+        timestamp_us % 1000000
+    )
+}
+
+pub fn ctime(timestamp_us: i64) -> UResult<String> {
+    let date_time = datetime_from_microseconds_since_boot(timestamp_us)?;
+    Ok(date_time.format("%a %b %d %H:%M:%S %Y").to_string())
+}
+
+pub fn iso(timestamp_us: i64) -> UResult<String> {
+    let date_time = datetime_from_microseconds_since_boot(timestamp_us)?;
+    Ok(date_time.format("%Y-%m-%dT%H:%M:%S,%6f%:z").to_string())
 }
 
-pub fn iso(timestamp_us: i64) -> String {
-    let date_time = boot_time()
-        .checked_add_signed(TimeDelta::microseconds(timestamp_us))
-        .unwrap();
-    date_time.format("%Y-%m-%dT%H:%M:%S,%6f%:z").to_string()
+/// The timestamp format of RFC 5424, which is RFC 3339 with microseconds.
+pub fn rfc3339(timestamp_us: i64) -> UResult<String> {
+    let date_time = datetime_from_microseconds_since_boot(timestamp_us)?;
+    Ok(date_time.format("%Y-%m-%dT%H:%M:%S%.6f%:z").to_string())
 }
 
 pub struct ReltimeFormatter {
     state: State,
     prev_timestamp_us: i64,
     previous_unix_timestamp: i64,
+    time_break: bool,
 }
 
 pub struct DeltaFormatter {
@@ -52,20 +69,19 @@ impl ReltimeFormatter {
             state: State::Initial,
             prev_timestamp_us: 0,
             previous_unix_timestamp: 0,
+            time_break: false,
         }
     }
 
-    pub fn format(&mut self, timestamp_us: i64) -> String {
-        let date_time = boot_time()
-            .checked_add_signed(TimeDelta::microseconds(timestamp_us))
-            .unwrap();
+    pub fn format(&mut self, timestamp_us: i64) -> UResult<String> {
+        let date_time = datetime_from_microseconds_since_boot(timestamp_us)?;
         let unix_timestamp = date_time.timestamp();
         let minute_changes = (unix_timestamp / 60) != (self.previous_unix_timestamp / 60);
+        self.time_break = matches!(self.state, State::Initial) || minute_changes;
         let format_res = match self.state {
-            State::Initial => date_time.format("%b%d %H:%M").to_string(),
-            _ if minute_changes => date_time.format("%b%d %H:%M").to_string(),
+            _ if self.time_break => date_time.format("%b%d %H:%M").to_string(),
             State::AfterBoot => Self::delta(0),
-            State::Delta => Self::delta(timestamp_us - self.prev_timestamp_us),
+            _ => Self::delta(timestamp_us - self.prev_timestamp_us),
         };
         self.prev_timestamp_us = timestamp_us;
         self.previous_unix_timestamp = unix_timestamp;
@@ -73,7 +89,12 @@ impl ReltimeFormatter {
             State::Initial if timestamp_us == 0 => State::AfterBoot,
             _ => State::Delta,
         };
-        format_res
+        Ok(format_res)
+    }
+
+    /// Whether the last timestamp was formatted as a date rather than as a delta.
+    pub fn is_time_break(&self) -> bool {
+        self.time_break
     }
 
     fn delta(delta_us: i64) -> String {
@@ -122,61 +143,131 @@ pub fn parse_datetime(s: &str) -> UResult<DateTime<FixedOffset>> {
         .map_err(|_| USimpleError::new(1, format!("invalid time value \"{s}\"")))
 }
 
-pub fn datetime_from_microseconds_since_boot(microseconds: i64) -> DateTime<FixedOffset> {
-    boot_time()
+pub fn datetime_from_microseconds_since_boot(microseconds: i64) -> UResult<DateTime<FixedOffset>> {
+    boot_time()?
         .checked_add_signed(TimeDelta::microseconds(microseconds))
-        .unwrap()
+        .ok_or_else(|| USimpleError::new(1, "time stamp out of range"))
+}
+
+pub fn microseconds_since_boot(date_time: DateTime<FixedOffset>) -> UResult<i64> {
+    Ok((date_time - boot_time()?)
+        .num_microseconds()
+        .unwrap_or(i64::MAX))
 }
 
-static BOOT_TIME: OnceLock<DateTime<FixedOffset>> = OnceLock::new();
+static BOOT_TIME: OnceLock<Option<DateTime<FixedOffset>>> = OnceLock::new();
+
+/// The wall time at which the timestamps of the kernel were zero.
+fn boot_time() -> UResult<DateTime<FixedOffset>> {
+    BOOT_TIME
+        .get_or_init(read_boot_time)
+        .ok_or_else(|| USimpleError::new(1, "cannot get boot time"))
+}
 
 #[cfg(feature = "fixed-boot-time")]
-fn boot_time() -> DateTime<FixedOffset> {
-    *BOOT_TIME.get_or_init(|| {
-        let date = NaiveDate::from_ymd_opt(2024, 11, 18).unwrap();
-        let time = NaiveTime::from_hms_micro_opt(19, 34, 12, 866807).unwrap();
-        let tz = FixedOffset::east_opt(7 * 3600).unwrap();
-        chrono::NaiveDateTime::new(date, time)
-            .and_local_timezone(tz)
-            .unwrap()
-    })
+fn read_boot_time() -> Option<DateTime<FixedOffset>> {
+    let date = NaiveDate::from_ymd_opt(2024, 11, 18).unwrap();
+    let time = NaiveTime::from_hms_micro_opt(19, 34, 12, 866807).unwrap();
+    let tz = FixedOffset::east_opt(7 * 3600).unwrap();
+    chrono::NaiveDateTime::new(date, time)
+        .and_local_timezone(tz)
+        .single()
+}
+
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+fn read_boot_time() -> Option<DateTime<FixedOffset>> {
+    let boot_time = boot_time_from_clocks().or_else(boot_time_from_proc_stat)?;
+    Some(boot_time.with_timezone(&chrono::Local).into())
 }
 
 #[cfg(not(feature = "fixed-boot-time"))]
 #[cfg(unix)]
-#[cfg(not(target_os = "openbsd"))]
-fn boot_time() -> DateTime<FixedOffset> {
-    *BOOT_TIME.get_or_init(|| boot_time_from_utmpx().unwrap())
+#[cfg(not(any(target_os = "linux", target_os = "openbsd")))]
+fn read_boot_time() -> Option<DateTime<FixedOffset>> {
+    boot_time_from_utmpx()
 }
 
 #[cfg(not(feature = "fixed-boot-time"))]
 #[cfg(windows)]
-fn boot_time() -> DateTime<FixedOffset> {
+fn read_boot_time() -> Option<DateTime<FixedOffset>> {
     // TODO: get windows boot time
-    *BOOT_TIME.get_or_init(|| chrono::DateTime::from_timestamp(0, 0).unwrap().into())
+    Some(chrono::DateTime::from_timestamp(0, 0)?.into())
 }
 
 #[cfg(not(feature = "fixed-boot-time"))]
 #[cfg(target_os = "openbsd")]
-fn boot_time() -> DateTime<FixedOffset> {
+fn read_boot_time() -> Option<DateTime<FixedOffset>> {
     // TODO: get openbsd boot time
-    *BOOT_TIME.get_or_init(|| chrono::DateTime::from_timestamp(0, 0).unwrap().into())
+    Some(chrono::DateTime::from_timestamp(0, 0)?.into())
+}
+
+/// The timestamps of the kernel stop while the system is suspended, like the monotonic clock,
+/// so they are only accurate since the last resume.
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+fn boot_time_from_clocks() -> Option<DateTime<Utc>> {
+    use uucore::libc;
+
+    let realtime = clock_gettime(libc::CLOCK_REALTIME)?;
+    let monotonic = clock_gettime(libc::CLOCK_MONOTONIC)?;
+    let boottime = clock_gettime(libc::CLOCK_BOOTTIME)?;
+    // Unlike the monotonic clock, the boottime clock also counts the time spent suspended
+    if boottime - monotonic >= TimeDelta::seconds(1) {
+        uucore::show_warning!(
+            "the system has been suspended, time stamps before the last resume may be inaccurate"
+        );
+    }
+    DateTime::UNIX_EPOCH.checked_add_signed(realtime - monotonic)
+}
+
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+fn clock_gettime(clock: uucore::libc::clockid_t) -> Option<TimeDelta> {
+    let mut time = uucore::libc::timespec {
+        tv_sec: 0,
+        tv_nsec: 0,
+    };
+    if unsafe { uucore::libc::clock_gettime(clock, &mut time) } != 0 {
+        return None;
+    }
+    // `time_t` is 32 bits on some targets
+    #[allow(clippy::unnecessary_cast)]
+    let seconds = time.tv_sec as i64;
+    TimeDelta::new(seconds, time.tv_nsec as u32)
+}
+
+/// The boot time in seconds, from the `btime` line.
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+fn boot_time_from_proc_stat() -> Option<DateTime<Utc>> {
+    let stat = std::fs::read_to_string("/proc/stat").ok()?;
+    let btime = stat.lines().find_map(|line| line.strip_prefix("btime "))?;
+    DateTime::from_timestamp(btime.trim().parse().ok()?, 0)
 }
 
 #[cfg(not(feature = "fixed-boot-time"))]
 #[cfg(unix)]
-#[cfg(not(target_os = "openbsd"))]
+#[cfg(not(any(target_os = "linux", target_os = "openbsd")))]
 fn boot_time_from_utmpx() -> Option<DateTime<FixedOffset>> {
-    for record in uucore::utmpx::Utmpx::iter_all_records() {
-        if record.record_type() == uucore::utmpx::BOOT_TIME {
-            let t = record.login_time();
-            return Some(
-                chrono::DateTime::from_timestamp(t.unix_timestamp(), t.nanosecond())
-                    .unwrap()
-                    .with_timezone(&chrono::Local)
-                    .into(),
-            );
-        }
+    let record = uucore::utmpx::Utmpx::iter_all_records()
+        .find(|record| record.record_type() == uucore::utmpx::BOOT_TIME)?;
+    let t = record.login_time();
+    Some(
+        chrono::DateTime::from_timestamp(t.unix_timestamp(), t.nanosecond())?
+            .with_timezone(&chrono::Local)
+            .into(),
+    )
+}
+
+#[cfg(not(feature = "fixed-boot-time"))]
+#[cfg(target_os = "linux")]
+#[test]
+fn test_boot_time_from_clocks() {
+    let boot_time = boot_time_from_clocks().unwrap();
+    assert!(boot_time < Utc::now());
+    // The btime line is rounded down to the second, and ignores suspends
+    if let Some(btime) = boot_time_from_proc_stat() {
+        assert!(boot_time >= btime - TimeDelta::seconds(1));
     }
-    None
 }
@@ -0,0 +1,136 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::{
+    fs,
+    io::{self, Write},
+    path::Path,
+};
+use uucore::error::{FromIo, UResult};
+
+use crate::sysfs::Snapshot;
+
+const TAR_BLOCK_SIZE: usize = 512;
+
+// Writes the snapshot as a plain directory tree, or as a tarball if the destination ends with `.tar`
+// Either way, the result can be passed back to lscpu with `--sysroot` (after extracting the tarball)
+pub fn write_snapshot(dest: &Path, snapshot: &Snapshot) -> UResult<()> {
+    let result = if dest.extension().is_some_and(|ext| ext == "tar") {
+        fs::File::create(dest).and_then(|file| write_tar(io::BufWriter::new(file), snapshot))
+    } else {
+        write_dir(dest, snapshot)
+    };
+    result.map_err_context(|| format!("cannot write snapshot to {}", dest.display()))
+}
+
+fn write_dir(dest: &Path, snapshot: &Snapshot) -> io::Result<()> {
+    fs::create_dir_all(dest)?;
+
+    for (path, content) in snapshot {
+        let path = dest.join(path);
+        match content {
+            Some(content) => {
+                if let Some(parent) = path.parent() {
+                    fs::create_dir_all(parent)?;
+                }
+                fs::write(path, content)?;
+            }
+            None => fs::create_dir_all(path)?,
+        }
+    }
+
+    Ok(())
+}
+
+// A minimal ustar writer, which is all we need for a handful of small regular files and directories
+fn write_tar(mut out: impl Write, snapshot: &Snapshot) -> io::Result<()> {
+    for (path, content) in snapshot {
+        let name = path.to_string_lossy();
+        match content {
+            Some(content) => {
+                out.write_all(&tar_header(&name, content.len(), b'0')?)?;
+                out.write_all(content)?;
+                let padding = (TAR_BLOCK_SIZE - content.len() % TAR_BLOCK_SIZE) % TAR_BLOCK_SIZE;
+                out.write_all(&vec![0; padding])?;
+            }
+            None => out.write_all(&tar_header(&format!("{}/", name), 0, b'5')?)?,
+        }
+    }
+
+    // The archive ends with two empty blocks
+    out.write_all(&[0; TAR_BLOCK_SIZE * 2])?;
+    out.flush()
+}
+
+fn tar_header(name: &str, size: usize, typeflag: u8) -> io::Result<[u8; TAR_BLOCK_SIZE]> {
+    let mut header = [0; TAR_BLOCK_SIZE];
+
+    // Names longer than 100 bytes have to be split on a `/` into a prefix and a name
+    let (prefix, name) = if name.len() <= 100 {
+        ("", name)
+    } else {
+        name.char_indices()
+            .filter(|(i, c)| *c == '/' && *i <= 155 && name.len() - i - 1 <= 100)
+            .map(|(i, _)| (&name[..i], &name[i + 1..]))
+            .next()
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidInput,
+                    format!("path too long: {}", name),
+                )
+            })?
+    };
+
+    let mode = if typeflag == b'5' { 0o755 } else { 0o644 };
+
+    header[..name.len()].copy_from_slice(name.as_bytes());
+    header[100..108].copy_from_slice(format!("{:07o}\0", mode).as_bytes());
+    header[108..116].copy_from_slice(b"0000000\0");
+    header[116..124].copy_from_slice(b"0000000\0");
+    header[124..136].copy_from_slice(format!("{:011o}\0", size).as_bytes());
+    header[136..148].copy_from_slice(b"00000000000\0");
+    header[156] = typeflag;
+    header[257..265].copy_from_slice(b"ustar\x0000");
+    header[345..345 + prefix.len()].copy_from_slice(prefix.as_bytes());
+
+    // The checksum is computed with its own field filled with spaces
+    header[148..156].copy_from_slice(b"        ");
+    let checksum: u32 = header.iter().map(|b| *b as u32).sum();
+    header[148..156].copy_from_slice(format!("{:06o}\0 ", checksum).as_bytes());
+
+    Ok(header)
+}
+
+#[test]
+fn test_write_tar() {
+    let mut snapshot = Snapshot::new();
+    snapshot.insert("proc".into(), None);
+    snapshot.insert("proc/cpuinfo".into(), Some(b"processor\t: 0\n".to_vec()));
+
+    let mut out = vec![];
+    write_tar(&mut out, &snapshot).unwrap();
+
+    // One header for the directory, a header and a data block for the file, and the end-of-archive marker
+    assert_eq!(out.len(), TAR_BLOCK_SIZE * 5);
+    assert!(out.starts_with(b"proc/\0"));
+    assert_eq!(out[156], b'5');
+    assert!(out[TAR_BLOCK_SIZE..].starts_with(b"proc/cpuinfo\0"));
+    assert_eq!(
+        &out[TAR_BLOCK_SIZE + 124..TAR_BLOCK_SIZE + 136],
+        b"00000000016\0"
+    );
+    assert!(out[TAR_BLOCK_SIZE * 2..].starts_with(b"processor\t: 0\n"));
+    assert!(out[TAR_BLOCK_SIZE * 3..].iter().all(|b| *b == 0));
+}
+
+#[test]
+fn test_tar_header_long_name() {
+    let name = format!("{}/{}", "a".repeat(120), "b".repeat(50));
+    let header = tar_header(&name, 0, b'0').unwrap();
+
+    assert!(header.starts_with(&[b'b'; 50]));
+    assert!(header[345..].starts_with(&[b'a'; 120]));
+    assert!(tar_header(&"a".repeat(200), 0, b'0').is_err());
+}
@@ -0,0 +1,272 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::fmt;
+
+// Names of the `HWCAP` and `HWCAP2` auxv bits on aarch64, as listed in the kernel's `asm/hwcap.h`
+const AARCH64_HWCAP: [&str; 33] = [
+    "fp", "asimd", "evtstrm", "aes", "pmull", "sha1", "sha2", "crc32", "atomics", "fphp",
+    "asimdhp", "cpuid", "asimdrdm", "jscvt", "fcma", "lrcpc", "dcpop", "sha3", "sm3", "sm4",
+    "asimddp", "sha512", "sve", "asimdfhm", "dit", "uscat", "ilrcpc", "flagm", "ssbs", "sb",
+    "paca", "pacg", "gcs",
+];
+
+const AARCH64_HWCAP2: [&str; 45] = [
+    "dcpodp",
+    "sve2",
+    "sveaes",
+    "svepmull",
+    "svebitperm",
+    "svesha3",
+    "svesm4",
+    "flagm2",
+    "frint",
+    "svei8mm",
+    "svef32mm",
+    "svef64mm",
+    "svebf16",
+    "i8mm",
+    "bf16",
+    "dgh",
+    "rng",
+    "bti",
+    "mte",
+    "ecv",
+    "afp",
+    "rpres",
+    "mte3",
+    "sme",
+    "smei16i64",
+    "smef64f64",
+    "smei8i32",
+    "smef16f32",
+    "smeb16f32",
+    "smef32f32",
+    "smefa64",
+    "wfxt",
+    "ebf16",
+    "sveebf16",
+    "cssc",
+    "rprfm",
+    "sve2p1",
+    "sme2",
+    "sme2p1",
+    "smei16i32",
+    "smebi32i32",
+    "smeb16b16",
+    "smef16f16",
+    "mops",
+    "hbc",
+];
+
+// Flags required by each x86-64 microarchitecture level, as defined by the x86-64 psABI
+// The names are the ones used by the kernel in `/proc/cpuinfo`, ie. `pni` is SSE3 and `abm` is LZCNT
+const X86_64_LEVELS: [&[&str]; 4] = [
+    &[
+        "lm", "cmov", "cx8", "fpu", "fxsr", "mmx", "syscall", "sse", "sse2",
+    ],
+    &[
+        "cx16", "lahf_lm", "popcnt", "pni", "sse4_1", "sse4_2", "ssse3",
+    ],
+    &[
+        "avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "abm", "movbe", "xsave",
+    ],
+    &["avx512f", "avx512bw", "avx512cd", "avx512dq", "avx512vl"],
+];
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum FlagCategory {
+    Simd,
+    Crypto,
+    Virtualization,
+    Security,
+    Other,
+}
+
+impl FlagCategory {
+    pub const ALL: [FlagCategory; 5] = [
+        FlagCategory::Simd,
+        FlagCategory::Crypto,
+        FlagCategory::Virtualization,
+        FlagCategory::Security,
+        FlagCategory::Other,
+    ];
+}
+
+impl fmt::Display for FlagCategory {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FlagCategory::Simd => write!(f, "SIMD"),
+            FlagCategory::Crypto => write!(f, "Crypto"),
+            FlagCategory::Virtualization => write!(f, "Virtualization"),
+            FlagCategory::Security => write!(f, "Security"),
+            FlagCategory::Other => write!(f, "Other"),
+        }
+    }
+}
+
+fn is_x86(architecture: &str) -> bool {
+    matches!(architecture, "x86_64" | "i386" | "i486" | "i586" | "i686")
+}
+
+fn is_aarch64(architecture: &str) -> bool {
+    matches!(architecture, "aarch64" | "arm64")
+}
+
+// The same flag name can mean different things on different architectures (ie. `sme` is memory encryption
+// on x86 but the matrix extension on aarch64), so the category tables are per-architecture
+pub fn categorize(architecture: &str, flag: &str) -> FlagCategory {
+    if is_x86(architecture) {
+        categorize_x86(flag)
+    } else if is_aarch64(architecture) {
+        categorize_aarch64(flag)
+    } else {
+        FlagCategory::Other
+    }
+}
+
+fn categorize_x86(flag: &str) -> FlagCategory {
+    match flag {
+        "aes" | "pclmulqdq" | "sha_ni" | "vaes" | "vpclmulqdq" | "gfni" | "rdrand" | "rdseed"
+        | "ace" | "ace_en" | "ace2" | "ace2_en" | "phe" | "phe_en" | "pmm" | "pmm_en" => {
+            FlagCategory::Crypto
+        }
+        "mmx" | "mmxext" | "sse" | "sse2" | "pni" | "ssse3" | "sse4_1" | "sse4_2" | "sse4a"
+        | "avx" | "avx2" | "fma" | "fma4" | "f16c" | "xop" | "avx_vnni" | "avx_vnni_int8"
+        | "avx_ne_convert" | "avx_ifma" | "3dnow" | "3dnowext" | "3dnowprefetch" => {
+            FlagCategory::Simd
+        }
+        f if f.starts_with("avx512") || f.starts_with("amx_") => FlagCategory::Simd,
+        "vmx" | "svm" | "hypervisor" | "ept" | "ept_ad" | "vpid" | "flexpriority"
+        | "tpr_shadow" | "vnmi" | "npt" | "nrip_save" | "lbrv" | "svm_lock" | "tsc_scale"
+        | "vmcb_clean" | "flushbyasid" | "decodeassists" | "pausefilter" | "pfthreshold"
+        | "avic" | "v_vmsave_vmload" | "vgif" | "x2avic" | "v_spec_ctrl" => {
+            FlagCategory::Virtualization
+        }
+        "nx" | "smep" | "smap" | "umip" | "pku" | "ospke" | "ibrs" | "ibpb" | "stibp" | "ssbd"
+        | "virt_ssbd" | "ibrs_enhanced" | "md_clear" | "flush_l1d" | "arch_capabilities"
+        | "pti" | "retpoline" | "spec_ctrl" | "intel_stibp" | "amd_ssbd" | "amd_ibrs"
+        | "amd_ibpb" | "amd_stibp" | "sme" | "sev" | "sev_es" | "sev_snp" | "tme" | "ibt"
+        | "shstk" | "user_shstk" | "sgx" | "sgx_lc" => FlagCategory::Security,
+        _ => FlagCategory::Other,
+    }
+}
+
+fn categorize_aarch64(flag: &str) -> FlagCategory {
+    match flag {
+        "aes" | "pmull" | "sha1" | "sha2" | "sha3" | "sha512" | "sm3" | "sm4" | "sveaes"
+        | "svepmull" | "svesha3" | "svesm4" | "rng" => FlagCategory::Crypto,
+        "fp" | "asimd" | "fphp" | "asimdhp" | "asimdrdm" | "asimddp" | "asimdfhm" | "fcma"
+        | "jscvt" | "i8mm" | "bf16" | "ebf16" | "frint" => FlagCategory::Simd,
+        f if f.starts_with("sve") || f.starts_with("sme") => FlagCategory::Simd,
+        "paca" | "pacg" | "bti" | "mte" | "mte3" | "sb" | "ssbs" | "dit" | "gcs" => {
+            FlagCategory::Security
+        }
+        _ => FlagCategory::Other,
+    }
+}
+
+// Returns the highest x86-64 microarchitecture level (1 to 4) whose requirements are all met by the flags
+pub fn x86_64_isa_level(flags: &[&str]) -> Option<usize> {
+    X86_64_LEVELS
+        .iter()
+        .take_while(|required| required.iter().all(|flag| flags.contains(flag)))
+        .count()
+        .checked_sub(1)
+        .map(|level| level + 1)
+}
+
+pub fn isa_level(architecture: &str, flags: &[&str]) -> Option<String> {
+    if architecture != "x86_64" {
+        return None;
+    }
+    x86_64_isa_level(flags).map(|level| format!("x86-64-v{}", level))
+}
+
+fn decode_bits(value: u64, names: &[&'static str]) -> Vec<&'static str> {
+    names
+        .iter()
+        .enumerate()
+        .filter(|(bit, _)| value & (1 << bit) != 0)
+        .map(|(_, name)| *name)
+        .collect()
+}
+
+// Names of the capabilities advertised in `AT_HWCAP` and `AT_HWCAP2`
+pub fn decode_aarch64_hwcaps(hwcap: u64, hwcap2: u64) -> Vec<&'static str> {
+    let mut caps = decode_bits(hwcap, &AARCH64_HWCAP);
+    caps.extend(decode_bits(hwcap2, &AARCH64_HWCAP2));
+    caps
+}
+
+// The hardware capabilities of the running machine, as passed to the process by the kernel in the auxv
+#[cfg(all(target_os = "linux", target_arch = "aarch64"))]
+pub fn read_hwcaps() -> Option<(u64, u64)> {
+    let hwcap = unsafe { libc::getauxval(libc::AT_HWCAP) };
+    let hwcap2 = unsafe { libc::getauxval(libc::AT_HWCAP2) };
+    Some((hwcap as u64, hwcap2 as u64))
+}
+
+#[cfg(not(all(target_os = "linux", target_arch = "aarch64")))]
+pub fn read_hwcaps() -> Option<(u64, u64)> {
+    None
+}
+
+#[test]
+fn test_x86_64_isa_level() {
+    let v1 = [
+        "fpu", "cx8", "cmov", "mmx", "fxsr", "sse", "sse2", "syscall", "lm",
+    ];
+    let v2 = [
+        &v1[..],
+        &[
+            "pni", "ssse3", "cx16", "sse4_1", "sse4_2", "popcnt", "lahf_lm",
+        ],
+    ]
+    .concat();
+    let v3 = [
+        &v2[..],
+        &[
+            "avx", "avx2", "bmi1", "bmi2", "f16c", "fma", "abm", "movbe", "xsave",
+        ],
+    ]
+    .concat();
+    // A v4 flag set without the v3 requirements doesn't make it past v2
+    let v2_with_avx512 = [
+        &v2[..],
+        &["avx512f", "avx512bw", "avx512cd", "avx512dq", "avx512vl"],
+    ]
+    .concat();
+
+    assert_eq!(x86_64_isa_level(&["fpu", "sse"]), None);
+    assert_eq!(x86_64_isa_level(&v1), Some(1));
+    assert_eq!(x86_64_isa_level(&v2), Some(2));
+    assert_eq!(x86_64_isa_level(&v3), Some(3));
+    assert_eq!(x86_64_isa_level(&v2_with_avx512), Some(2));
+    assert_eq!(isa_level("x86_64", &v3).unwrap(), "x86-64-v3");
+    assert_eq!(isa_level("aarch64", &v3), None);
+}
+
+#[test]
+fn test_categorize() {
+    assert_eq!(categorize("x86_64", "avx512vl"), FlagCategory::Simd);
+    assert_eq!(categorize("x86_64", "sha_ni"), FlagCategory::Crypto);
+    assert_eq!(categorize("x86_64", "vmx"), FlagCategory::Virtualization);
+    assert_eq!(categorize("x86_64", "sme"), FlagCategory::Security);
+    assert_eq!(categorize("x86_64", "tsc"), FlagCategory::Other);
+    assert_eq!(categorize("aarch64", "sme"), FlagCategory::Simd);
+    assert_eq!(categorize("aarch64", "sveaes"), FlagCategory::Crypto);
+    assert_eq!(categorize("aarch64", "bti"), FlagCategory::Security);
+    assert_eq!(categorize("riscv64", "aes"), FlagCategory::Other);
+}
+
+#[test]
+fn test_decode_aarch64_hwcaps() {
+    assert_eq!(
+        decode_aarch64_hwcaps(0b1011, 0b10),
+        ["fp", "asimd", "aes", "sve2"]
+    );
+    assert!(decode_aarch64_hwcaps(0, 0).is_empty());
+}
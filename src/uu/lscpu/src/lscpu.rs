@@ -3,46 +3,88 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use clap::{crate_version, Arg, ArgAction, Command};
+use clap::builder::{EnumValueParser, PossibleValue};
+use clap::{crate_version, Arg, ArgAction, ArgMatches, Command, ValueEnum};
 use regex::RegexBuilder;
-use serde::Serialize;
-use std::{cmp, collections::HashMap, fs};
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::{collections::BTreeMap, io::IsTerminal, path::Path};
 use sysfs::CacheSize;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uucore::{
+    error::{set_exit_code, UResult},
+    format_usage, help_about, help_usage,
+};
 
 mod options {
     pub const BYTES: &str = "bytes";
+    pub const CACHES: &str = "caches";
+    pub const DUMP: &str = "dump";
+    pub const EXIT_ON_VULNERABLE: &str = "exit-on-vulnerable";
+    pub const EXTENDED: &str = "extended";
+    pub const FLAGS: &str = "flags";
     pub const HEX: &str = "hex";
+    pub const HIERARCHIC: &str = "hierarchic";
     pub const JSON: &str = "json";
+    pub const PARSE: &str = "parse";
+    pub const SYSROOT: &str = "sysroot";
 }
 
+mod dump;
+mod flags;
 mod sysfs;
+mod virt;
 
 const ABOUT: &str = help_about!("lscpu.md");
 const USAGE: &str = help_usage!("lscpu.md");
 
+// Distinct from the status used for errors, so that a compliance check can tell both apart
+const EXIT_VULNERABLE: i32 = 2;
+
 #[derive(Serialize)]
 struct CpuInfos {
     lscpu: Vec<CpuInfo>,
 }
 
+// Mirrors the util-linux JSON schema, where field names keep their trailing colon and empty data is `null`
 #[derive(Serialize)]
 struct CpuInfo {
+    #[serde(serialize_with = "serialize_field")]
     field: String,
+    #[serde(serialize_with = "serialize_data")]
     data: String,
+    #[serde(flatten)]
+    vulnerability: Option<VulnerabilityInfo>,
     #[serde(skip_serializing_if = "Vec::is_empty")]
     children: Vec<CpuInfo>,
 }
 
+// Extra fields for entries of the vulnerabilities section, so that scripts don't have to parse the description
+#[derive(Serialize)]
+struct VulnerabilityInfo {
+    status: String,
+    details: Option<String>,
+}
+
 impl CpuInfo {
     fn new(field: &str, data: &str) -> Self {
         Self {
             field: field.to_string(),
             data: data.to_string(),
+            vulnerability: None,
             children: Vec::new(),
         }
     }
 
+    fn from_vulnerability(vuln: &sysfs::CpuVulnerability) -> Self {
+        Self {
+            vulnerability: Some(VulnerabilityInfo {
+                status: vuln.status.to_string(),
+                details: vuln.details.clone(),
+            }),
+            ..Self::new(&vuln.name, &vuln.description)
+        }
+    }
+
     fn add_child(&mut self, child: Self) {
         self.children.push(child);
     }
@@ -59,14 +101,306 @@ impl CpuInfos {
         self.lscpu.push(cpu_info);
     }
 
-    fn to_json(&self) -> String {
-        serde_json::to_string_pretty(self).unwrap()
+    fn to_json(&self) -> Value {
+        serde_json::to_value(self).unwrap()
+    }
+
+    // Turns the tree into a flat list of entries, the way util-linux prints the summary without subsections
+    // Entries that only group other entries are dropped, and their children are renamed to stay unambiguous
+    fn flatten(self) -> CpuInfos {
+        fn flatten_into(out: &mut Vec<CpuInfo>, mut info: CpuInfo, section: Option<&str>) {
+            if info.data.is_empty() && !info.children.is_empty() {
+                for child in info.children {
+                    flatten_into(out, child, Some(&info.field));
+                }
+                return;
+            }
+
+            let children = std::mem::take(&mut info.children);
+            info.field = match section {
+                Some("Caches (sum of all)") => format!("{} cache", info.field),
+                Some("Vulnerabilities") => format!("Vulnerability {}", info.field),
+                _ => info.field,
+            };
+            out.push(info);
+
+            for child in children {
+                flatten_into(out, child, None);
+            }
+        }
+
+        let mut out = vec![];
+        for info in self.lscpu {
+            flatten_into(&mut out, info, None);
+        }
+        CpuInfos { lscpu: out }
+    }
+}
+
+fn serialize_field<S: Serializer>(field: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&format!("{}:", field))
+}
+
+fn serialize_data<S: Serializer>(data: &str, serializer: S) -> Result<S::Ok, S::Error> {
+    if data.is_empty() {
+        serializer.serialize_none()
+    } else {
+        serializer.serialize_str(data)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+    Cpu,
+    Drawer,
+    Book,
+    Socket,
+    Die,
+    Cluster,
+    Core,
+    Node,
+    Cache,
+    MaxMhz,
+    MinMhz,
+    Mhz,
+    ScalMhz,
+}
+
+impl ValueEnum for Column {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            Column::Cpu,
+            Column::Drawer,
+            Column::Book,
+            Column::Socket,
+            Column::Die,
+            Column::Cluster,
+            Column::Core,
+            Column::Node,
+            Column::Cache,
+            Column::MaxMhz,
+            Column::MinMhz,
+            Column::Mhz,
+            Column::ScalMhz,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.get_name()))
+    }
+}
+
+impl Column {
+    fn get_name(&self) -> &'static str {
+        match self {
+            Column::Cpu => "CPU",
+            Column::Drawer => "DRAWER",
+            Column::Book => "BOOK",
+            Column::Socket => "SOCKET",
+            Column::Die => "DIE",
+            Column::Cluster => "CLUSTER",
+            Column::Core => "CORE",
+            Column::Node => "NODE",
+            Column::Cache => "CACHE",
+            Column::MaxMhz => "MAXMHZ",
+            Column::MinMhz => "MINMHZ",
+            Column::Mhz => "MHZ",
+            Column::ScalMhz => "SCALMHZ%",
+        }
+    }
+
+    fn get_help(&self) -> &'static str {
+        match self {
+            Column::Cpu => "logical CPU number",
+            Column::Drawer => "logical drawer number",
+            Column::Book => "logical book number",
+            Column::Socket => "logical socket number",
+            Column::Die => "logical die number",
+            Column::Cluster => "logical cluster number",
+            Column::Core => "logical core number",
+            Column::Node => "logical NUMA node number",
+            Column::Cache => "shows how caches are shared between CPUs",
+            Column::MaxMhz => "shows the maximum MHz of the CPU",
+            Column::MinMhz => "shows the minimum MHz of the CPU",
+            Column::Mhz => "shows the current MHz of the CPU",
+            Column::ScalMhz => "shows scaling percentage of the CPU frequency",
+        }
+    }
+
+    // The cache column is named after the caches it lists, like `L1d:L1i:L2:L3`
+    fn get_header(&self, caches: &CacheGroups) -> String {
+        match self {
+            Column::Cache => caches.keys().cloned().collect::<Vec<_>>().join(":"),
+            _ => self.get_name().to_string(),
+        }
+    }
+
+    fn get_value(&self, cpu: &sysfs::Cpu, caches: &CacheGroups) -> Option<String> {
+        match self {
+            Column::Cpu => Some(cpu.index.to_string()),
+            Column::Drawer => cpu.drawer_id.map(|id| id.to_string()),
+            Column::Book => cpu.book_id.map(|id| id.to_string()),
+            Column::Socket => cpu.pkg_id.map(|id| id.to_string()),
+            Column::Die => cpu.die_id.map(|id| id.to_string()),
+            Column::Cluster => cpu.cluster_id.map(|id| id.to_string()),
+            Column::Core => cpu.core_id.map(|id| id.to_string()),
+            Column::Node => cpu.node_id.map(|id| id.to_string()),
+            Column::Cache => Some(cache_ids(cpu, caches).join(":")).filter(|ids| !ids.is_empty()),
+            Column::MaxMhz => cpu.freq.max_mhz.map(format_mhz),
+            Column::MinMhz => cpu.freq.min_mhz.map(format_mhz),
+            Column::Mhz => cpu.freq.cur_mhz.map(format_mhz),
+            Column::ScalMhz => cpu.freq.scaling_percent().map(format_percent),
+        }
+    }
+
+    // Frequencies are kept as numbers, with the same rounding as in the text output
+    fn get_json_type(&self) -> smartcols::JsonType {
+        match self {
+            Column::Cache | Column::ScalMhz => smartcols::JsonType::String,
+            _ => smartcols::JsonType::Number,
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum CacheColumn {
+    Name,
+    OneSize,
+    AllSize,
+    Ways,
+    Type,
+    Level,
+    Sets,
+    PhyLine,
+    CoherencySize,
+}
+
+impl ValueEnum for CacheColumn {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            CacheColumn::Name,
+            CacheColumn::OneSize,
+            CacheColumn::AllSize,
+            CacheColumn::Ways,
+            CacheColumn::Type,
+            CacheColumn::Level,
+            CacheColumn::Sets,
+            CacheColumn::PhyLine,
+            CacheColumn::CoherencySize,
+        ]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(PossibleValue::new(self.get_name()))
+    }
+}
+
+impl CacheColumn {
+    fn get_name(&self) -> &'static str {
+        match self {
+            CacheColumn::Name => "NAME",
+            CacheColumn::OneSize => "ONE-SIZE",
+            CacheColumn::AllSize => "ALL-SIZE",
+            CacheColumn::Ways => "WAYS",
+            CacheColumn::Type => "TYPE",
+            CacheColumn::Level => "LEVEL",
+            CacheColumn::Sets => "SETS",
+            CacheColumn::PhyLine => "PHY-LINE",
+            CacheColumn::CoherencySize => "COHERENCY-SIZE",
+        }
+    }
+
+    fn get_help(&self) -> &'static str {
+        match self {
+            CacheColumn::Name => "cache name",
+            CacheColumn::OneSize => "size of one cache",
+            CacheColumn::AllSize => "size of all system caches",
+            CacheColumn::Ways => "ways of associativity",
+            CacheColumn::Type => "cache type",
+            CacheColumn::Level => "cache level",
+            CacheColumn::Sets => "number of sets",
+            CacheColumn::PhyLine => "number of physical cache line per cache tag",
+            CacheColumn::CoherencySize => {
+                "minimum amount of data in bytes transferred from memory to cache"
+            }
+        }
+    }
+
+    fn is_right_aligned(&self) -> bool {
+        !matches!(self, CacheColumn::Name | CacheColumn::Type)
+    }
+
+    fn get_value(&self, cache: &CacheRow, bytes: bool) -> Option<String> {
+        let size = |size: u64| {
+            let size = CacheSize::new(size);
+            if bytes {
+                size.raw()
+            } else {
+                size.human_readable()
+            }
+        };
+        match self {
+            CacheColumn::Name => Some(cache.name.clone()),
+            CacheColumn::OneSize => Some(size(cache.one_size)),
+            CacheColumn::AllSize => Some(size(cache.all_size)),
+            CacheColumn::Ways => cache.ways.map(|v| v.to_string()),
+            CacheColumn::Type => Some(cache.typ.to_string()),
+            CacheColumn::Level => Some(cache.level.to_string()),
+            CacheColumn::Sets => cache.sets.map(|v| v.to_string()),
+            CacheColumn::PhyLine => cache.phy_line.map(|v| v.to_string()),
+            CacheColumn::CoherencySize => cache.coherency_size.map(|v| v.to_string()),
+        }
+    }
+
+    fn get_json_type(&self, bytes: bool) -> smartcols::JsonType {
+        match self {
+            CacheColumn::Name | CacheColumn::Type => smartcols::JsonType::String,
+            CacheColumn::OneSize | CacheColumn::AllSize if !bytes => smartcols::JsonType::String,
+            _ => smartcols::JsonType::Number,
+        }
+    }
+}
+
+// One line of the `--caches` table, describing all instances of a given cache across the system
+struct CacheRow {
+    name: String,
+    one_size: u64,
+    all_size: u64,
+    ways: Option<u64>,
+    typ: String,
+    level: usize,
+    sets: Option<u64>,
+    phy_line: Option<u64>,
+    coherency_size: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Hierarchic {
+    Auto,
+    Always,
+    Never,
+}
+
+impl ValueEnum for Hierarchic {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Hierarchic::Auto, Hierarchic::Always, Hierarchic::Never]
+    }
+
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        match self {
+            Hierarchic::Auto => {
+                Some(PossibleValue::new("auto").help("use subsections when writing to a terminal"))
+            }
+            Hierarchic::Always => Some(PossibleValue::new("always").help("always use subsections")),
+            Hierarchic::Never => Some(PossibleValue::new("never").help("never use subsections")),
+        }
     }
 }
 
 struct OutputOptions {
     bytes: bool,
     json: bool,
+    hierarchic: bool,
     _hex: bool,
 }
 
@@ -74,78 +408,235 @@ struct OutputOptions {
 pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     let matches: clap::ArgMatches = uu_app().try_get_matches_from(args)?;
 
+    let json = matches.get_flag(options::JSON);
+    let hierarchic = match matches.get_one::<Hierarchic>(options::HIERARCHIC) {
+        Some(Hierarchic::Always) => true,
+        Some(Hierarchic::Never) => false,
+        // Like in util-linux, output sent to a pipe has no subsections, which keeps it easy to parse
+        _ => std::io::stdout().is_terminal(),
+    };
+
     let output_opts = OutputOptions {
         bytes: matches.get_flag(options::BYTES),
         _hex: matches.get_flag(options::HEX),
-        json: matches.get_flag(options::JSON),
+        json,
+        hierarchic,
     };
 
-    let mut cpu_infos = CpuInfos::new();
+    let sysroot = match matches.get_one::<String>(options::SYSROOT) {
+        Some(dir) => sysfs::SysRoot::new(dir),
+        None => sysfs::SysRoot::new("/"),
+    };
+
+    let dump = matches.get_one::<String>(options::DUMP);
+    let sysroot = if dump.is_some() {
+        sysroot.record()
+    } else {
+        sysroot
+    };
 
-    let mut arch_info = CpuInfo::new("Architecture", &get_architecture());
+    let vulns = lscpu(&matches, &sysroot, &output_opts)?;
+
+    if matches.get_flag(options::EXIT_ON_VULNERABLE) {
+        // Only the summary shows the vulnerabilities, other modes read them just for the check
+        let vulns = vulns.unwrap_or_else(|| sysfs::read_cpu_vulnerabilities(&sysroot));
+        if vulns.iter().any(|vuln| vuln.status.is_unmitigated()) {
+            set_exit_code(EXIT_VULNERABLE);
+        }
+    }
+
+    if let Some(dest) = dump {
+        read_all(&sysroot, &output_opts)?;
+        dump::write_snapshot(Path::new(dest), &sysroot.take_snapshot())?;
+    }
+
+    Ok(())
+}
+
+fn lscpu(
+    matches: &ArgMatches,
+    sysroot: &sysfs::SysRoot,
+    output_opts: &OutputOptions,
+) -> UResult<Option<Vec<sysfs::CpuVulnerability>>> {
+    for mode in [options::EXTENDED, options::PARSE] {
+        if matches.contains_id(mode) {
+            let cpu_topology = sysfs::CpuTopology::new(sysroot)?;
+            let mut columns: Vec<Column> = matches
+                .get_many::<Column>(mode)
+                .unwrap_or_default()
+                .copied()
+                .collect();
+            let compat = columns.is_empty() && mode == options::PARSE;
+            if compat {
+                columns = default_parse_columns();
+            } else if columns.is_empty() {
+                columns = default_extended_columns(&cpu_topology);
+            }
+            if mode == options::PARSE && !output_opts.json {
+                print_parse(&cpu_topology, &columns, compat);
+            } else {
+                print_extended(&cpu_topology, &columns, output_opts);
+            }
+            return Ok(None);
+        }
+    }
+
+    if matches.contains_id(options::CACHES) {
+        let cpu_topology = sysfs::CpuTopology::new(sysroot)?;
+        let mut columns: Vec<CacheColumn> = matches
+            .get_many::<CacheColumn>(options::CACHES)
+            .unwrap_or_default()
+            .copied()
+            .collect();
+        if columns.is_empty() {
+            columns = CacheColumn::value_variants().to_vec();
+        }
+        print_caches(&cpu_topology, &columns, output_opts);
+        return Ok(None);
+    }
+
+    let architecture = get_architecture(sysroot);
 
     // TODO: We just silently ignore failures to read `/proc/cpuinfo` currently and treat it as empty
     // Perhaps a better solution should be put in place, but what?
-    let contents = fs::read_to_string("/proc/cpuinfo").unwrap_or_default();
+    let contents = sysroot.read_to_string("/proc/cpuinfo").unwrap_or_default();
+
+    if matches.get_flag(options::FLAGS) {
+        print_output(
+            get_flags_info(sysroot, &architecture, &contents),
+            output_opts,
+        );
+        return Ok(None);
+    }
+
+    let vulns = sysfs::read_cpu_vulnerabilities(sysroot);
+    print_output(
+        get_summary(sysroot, &architecture, &contents, &vulns, output_opts)?,
+        output_opts,
+    );
+
+    Ok(Some(vulns))
+}
 
-    if let Some(addr_sizes) = find_cpuinfo_value(&contents, "address sizes") {
+// A dump has to replay every mode and not only the one that ran, so this goes through the files all of them read
+// The summary covers the CPU topology, caches, virtualization and vulnerabilities, which leaves the flags
+fn read_all(sysroot: &sysfs::SysRoot, output_opts: &OutputOptions) -> UResult<()> {
+    let architecture = get_architecture(sysroot);
+    let contents = sysroot.read_to_string("/proc/cpuinfo").unwrap_or_default();
+    get_flags_info(sysroot, &architecture, &contents);
+    let vulns = sysfs::read_cpu_vulnerabilities(sysroot);
+    get_summary(sysroot, &architecture, &contents, &vulns, output_opts)?;
+    Ok(())
+}
+
+fn get_summary(
+    sysroot: &sysfs::SysRoot,
+    architecture: &str,
+    contents: &str,
+    vulns: &[sysfs::CpuVulnerability],
+    output_opts: &OutputOptions,
+) -> UResult<CpuInfos> {
+    let mut cpu_infos = CpuInfos::new();
+    let mut arch_info = CpuInfo::new("Architecture", architecture);
+
+    if let Some(op_modes) = get_op_modes(architecture, contents, sysroot.is_live()) {
+        arch_info.add_child(CpuInfo::new("CPU op-mode(s)", &op_modes));
+    }
+
+    if let Some(addr_sizes) = find_cpuinfo_value(contents, "address sizes") {
         arch_info.add_child(CpuInfo::new("Address sizes", &addr_sizes))
     }
 
-    if let Some(byte_order) = sysfs::read_cpu_byte_order() {
+    if let Some(byte_order) = sysfs::read_cpu_byte_order(sysroot) {
         arch_info.add_child(CpuInfo::new("Byte Order", byte_order));
     }
 
     cpu_infos.push(arch_info);
 
-    let cpu_topology = sysfs::CpuTopology::new();
+    let cpu_topology = sysfs::CpuTopology::new(sysroot)?;
     let mut cores_info = CpuInfo::new("CPU(s)", &format!("{}", cpu_topology.cpus.len()));
 
     cores_info.add_child(CpuInfo::new(
         "On-line CPU(s) list",
-        &sysfs::read_online_cpus(),
+        &sysfs::read_online_cpus(sysroot)?,
     ));
 
     cpu_infos.push(cores_info);
 
+    // The topology and frequencies are described under the model name, when there is one
+    let mut model_infos = vec![];
+
+    let socket_count = cpu_topology.socket_count();
+    let core_count = cpu_topology.core_count();
+
+    if let Some(core_count) = core_count {
+        model_infos.push(CpuInfo::new(
+            "Thread(s) per core",
+            &(cpu_topology.cpus.len() / core_count).to_string(),
+        ));
+    }
+
+    if let Some(socket_count) = socket_count {
+        if let Some(core_count) = core_count {
+            model_infos.push(CpuInfo::new(
+                "Core(s) per socket",
+                &(core_count / socket_count).to_string(),
+            ));
+        }
+        model_infos.push(CpuInfo::new("Socket(s)", &socket_count.to_string()));
+    }
+
+    for (name, count) in [
+        ("Die(s)", cpu_topology.die_count()),
+        ("Cluster(s)", cpu_topology.cluster_count()),
+        ("Book(s)", cpu_topology.book_count()),
+        ("Drawer(s)", cpu_topology.drawer_count()),
+    ] {
+        if let Some(count) = count {
+            model_infos.push(CpuInfo::new(name, &count.to_string()));
+        }
+    }
+
+    if let Some(freq_boost_enabled) = sysfs::read_freq_boost_state(sysroot) {
+        let s = if freq_boost_enabled {
+            "enabled"
+        } else {
+            "disabled"
+        };
+        model_infos.push(CpuInfo::new("Frequency boost", s));
+    }
+
+    if let Some(scaling) = cpu_topology.freq_scaling_percent() {
+        model_infos.push(CpuInfo::new("CPU(s) scaling MHz", &format_percent(scaling)));
+    }
+
+    if let Some(max_mhz) = cpu_topology.max_freq_mhz() {
+        model_infos.push(CpuInfo::new("CPU max MHz", &format_mhz(max_mhz)));
+    }
+
+    if let Some(min_mhz) = cpu_topology.min_freq_mhz() {
+        model_infos.push(CpuInfo::new("CPU min MHz", &format_mhz(min_mhz)));
+    }
+
     // TODO: This is currently quite verbose and doesn't strictly respect the hierarchy of `/proc/cpuinfo` contents
     // ie. the file might contain multiple sections, each with their own vendor_id/model name etc. but right now
     // we're just taking whatever our regex matches first and using that
-    if let Some(vendor) = find_cpuinfo_value(&contents, "vendor_id") {
+    if let Some(vendor) = find_cpuinfo_value(contents, "vendor_id") {
         let mut vendor_info = CpuInfo::new("Vendor ID", &vendor);
 
-        if let Some(model_name) = find_cpuinfo_value(&contents, "model name") {
+        if let Some(model_name) = find_cpuinfo_value(contents, "model name") {
             let mut model_name_info = CpuInfo::new("Model name", &model_name);
 
-            if let Some(family) = find_cpuinfo_value(&contents, "cpu family") {
+            if let Some(family) = find_cpuinfo_value(contents, "cpu family") {
                 model_name_info.add_child(CpuInfo::new("CPU Family", &family));
             }
 
-            if let Some(model) = find_cpuinfo_value(&contents, "model") {
+            if let Some(model) = find_cpuinfo_value(contents, "model") {
                 model_name_info.add_child(CpuInfo::new("Model", &model));
             }
 
-            let socket_count = &cpu_topology.socket_count();
-            let core_count = &cpu_topology.core_count();
-
-            model_name_info.add_child(CpuInfo::new(
-                "Thread(s) per core",
-                &(cpu_topology.cpus.len() / core_count).to_string(),
-            ));
-
-            model_name_info.add_child(CpuInfo::new(
-                "Core(s) per socket",
-                &(core_count / socket_count).to_string(),
-            ));
-            model_name_info.add_child(CpuInfo::new("Socket(s)", &socket_count.to_string()));
-
-            if let Some(freq_boost_enabled) = sysfs::read_freq_boost_state() {
-                let s = if freq_boost_enabled {
-                    "enabled"
-                } else {
-                    "disabled"
-                };
-                model_name_info.add_child(CpuInfo::new("Frequency boost", s));
+            for info in model_infos.drain(..) {
+                model_name_info.add_child(info);
             }
 
             vendor_info.add_child(model_name_info);
@@ -154,55 +645,133 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
         cpu_infos.push(vendor_info);
     }
 
-    if let Some(cache_info) = calculate_cache_totals(cpu_topology.cpus, &output_opts) {
+    // Without a model name to nest them under, as on aarch64 or s390x, they go to the top level like in util-linux
+    for info in model_infos {
+        cpu_infos.push(info);
+    }
+
+    let virtualization = virt::detect(
+        sysroot,
+        &find_cpuinfo_value(contents, "flags").unwrap_or_default(),
+    );
+    if !virtualization.is_empty() {
+        let mut virt_info = CpuInfo::new("Virtualization features", "");
+        if let Some(extensions) = virtualization.extensions {
+            virt_info.add_child(CpuInfo::new("Virtualization", extensions));
+        }
+        if let Some(vendor) = &virtualization.vendor {
+            virt_info.add_child(CpuInfo::new("Hypervisor vendor", vendor));
+        }
+        if let Some(typ) = &virtualization.typ {
+            virt_info.add_child(CpuInfo::new("Virtualization type", &typ.to_string()));
+        }
+        cpu_infos.push(virt_info);
+    }
+
+    if let Some(cache_info) = calculate_cache_totals(&cpu_topology.cpus, output_opts) {
         cpu_infos.push(cache_info);
     }
 
-    let vulns = sysfs::read_cpu_vulnerabilities();
     if !vulns.is_empty() {
         let mut vuln_info = CpuInfo::new("Vulnerabilities", "");
         for vuln in vulns {
-            vuln_info.add_child(CpuInfo::new(&vuln.name, &vuln.mitigation));
+            vuln_info.add_child(CpuInfo::from_vulnerability(vuln));
         }
         cpu_infos.push(vuln_info);
     }
 
-    print_output(cpu_infos, output_opts);
-
-    Ok(())
+    Ok(cpu_infos)
 }
 
-fn calculate_cache_totals(cpus: Vec<sysfs::Cpu>, out_opts: &OutputOptions) -> Option<CpuInfo> {
-    let mut by_levels: HashMap<String, Vec<&sysfs::CpuCache>> = HashMap::new();
-    let all_caches: Vec<_> = cpus.iter().flat_map(|cpu| &cpu.caches).collect();
+// Lists the CPU flags grouped by category, along with the ISA level they imply where that's meaningful
+// On aarch64 `/proc/cpuinfo` has a `Features` line instead of `flags`, and the capabilities are also read from the auxv
+fn get_flags_info(sysroot: &sysfs::SysRoot, architecture: &str, cpuinfo: &str) -> CpuInfos {
+    let mut cpu_infos = CpuInfos::new();
 
-    if all_caches.is_empty() {
-        return None;
+    let cpuinfo_flags = find_cpuinfo_value(cpuinfo, "flags")
+        .or_else(|| find_cpuinfo_value(cpuinfo, "Features"))
+        .unwrap_or_default();
+    let mut cpu_flags: Vec<&str> = cpuinfo_flags.split_whitespace().collect();
+
+    // The auxv describes the running machine, so it has nothing to say about another system root
+    let hwcaps = flags::read_hwcaps().filter(|_| sysroot.is_live());
+    if let Some((hwcap, hwcap2)) = hwcaps {
+        cpu_infos.push(CpuInfo::new("HWCAP", &format!("{:#x}", hwcap)));
+        cpu_infos.push(CpuInfo::new("HWCAP2", &format!("{:#x}", hwcap2)));
+        for cap in flags::decode_aarch64_hwcaps(hwcap, hwcap2) {
+            if !cpu_flags.contains(&cap) {
+                cpu_flags.push(cap);
+            }
+        }
     }
 
-    for cache in all_caches {
-        let type_suffix = match cache.typ {
-            sysfs::CacheType::Instruction => "i",
-            sysfs::CacheType::Data => "d",
-            _ => "",
-        };
-        let level_key = format!("L{}{}", cache.level, type_suffix);
+    if let Some(level) = flags::isa_level(architecture, &cpu_flags) {
+        cpu_infos.push(CpuInfo::new("ISA level", &level));
+    }
 
-        if let Some(caches) = by_levels.get_mut(&level_key) {
-            caches.push(cache);
-        } else {
-            by_levels.insert(level_key, vec![cache]);
+    for category in flags::FlagCategory::ALL {
+        let names: Vec<_> = cpu_flags
+            .iter()
+            .copied()
+            .filter(|flag| flags::categorize(architecture, flag) == category)
+            .collect();
+        if !names.is_empty() {
+            cpu_infos.push(CpuInfo::new(&category.to_string(), &names.join(" ")));
         }
     }
 
-    let mut cache_info = CpuInfo::new("Caches (sum of all)", "");
+    cpu_infos
+}
+
+// Groups all cache instances by their name (ie. `L1d`), sorted alphabetically
+// Cache instances that are shared across multiple CPUs should have the same `shared_cpu_map` value
+// Deduplicating the list on a per-level basic using the CPU map ensures that we don't count any shared caches multiple times
+type CacheGroups<'a> = BTreeMap<String, Vec<&'a sysfs::CpuCache>>;
+
+fn group_caches(cpus: &[sysfs::Cpu]) -> CacheGroups<'_> {
+    let mut by_levels: CacheGroups = BTreeMap::new();
 
-    for (level, caches) in by_levels.iter_mut() {
-        // Cache instances that are shared across multiple CPUs should have the same `shared_cpu_map` value
-        // Deduplicating the list on a per-level basic using the CPU map ensures that we don't count any shared caches multiple times
+    for cache in cpus.iter().flat_map(|cpu| &cpu.caches) {
+        by_levels.entry(cache.name()).or_default().push(cache);
+    }
+
+    for caches in by_levels.values_mut() {
         caches.sort_by(|a, b| a.shared_cpu_map.cmp(&b.shared_cpu_map));
         caches.dedup_by_key(|c| &c.shared_cpu_map);
+    }
+
+    by_levels
+}
+
+// A cache's ID is its index among the distinct caches of the same name, empty if the CPU has none
+fn cache_ids(cpu: &sysfs::Cpu, caches: &CacheGroups) -> Vec<String> {
+    caches
+        .iter()
+        .map(|(name, instances)| {
+            cpu.caches
+                .iter()
+                .find(|cache| cache.name() == *name)
+                .and_then(|cache| {
+                    instances
+                        .iter()
+                        .position(|instance| instance.shared_cpu_map == cache.shared_cpu_map)
+                })
+                .map(|id| id.to_string())
+                .unwrap_or_default()
+        })
+        .collect()
+}
+
+fn calculate_cache_totals(cpus: &[sysfs::Cpu], out_opts: &OutputOptions) -> Option<CpuInfo> {
+    let by_levels = group_caches(cpus);
+
+    if by_levels.is_empty() {
+        return None;
+    }
+
+    let mut cache_info = CpuInfo::new("Caches (sum of all)", "");
 
+    for (level, caches) in by_levels {
         let count = caches.len();
         let size_total = caches
             .iter()
@@ -210,7 +779,7 @@ fn calculate_cache_totals(cpus: Vec<sysfs::Cpu>, out_opts: &OutputOptions) -> Op
 
         let size = CacheSize::new(size_total);
         cache_info.add_child(CpuInfo::new(
-            level,
+            &level,
             &format!(
                 "{} ({} instances)",
                 if out_opts.bytes {
@@ -223,69 +792,217 @@ fn calculate_cache_totals(cpus: Vec<sysfs::Cpu>, out_opts: &OutputOptions) -> Op
         ));
     }
 
-    // Make sure caches get printed in alphabetical order
-    cache_info.children.sort_by(|a, b| a.field.cmp(&b.field));
-
     Some(cache_info)
 }
 
-fn print_output(infos: CpuInfos, out_opts: OutputOptions) {
+fn print_output(infos: CpuInfos, out_opts: &OutputOptions) {
+    let infos = if out_opts.hierarchic {
+        infos
+    } else {
+        infos.flatten()
+    };
+
     if out_opts.json {
-        println!("{}", infos.to_json());
+        println!("{}", smartcols::json::to_string(&infos.to_json()));
         return;
     }
 
-    fn indentation(depth: usize) -> usize {
-        // Indentation is 2 spaces per level, used in a few places, hence its own helper function
-        depth * 2
+    fn to_line(info: CpuInfo) -> smartcols::Line {
+        let mut line = smartcols::Line::new([Some(format!("{}:", info.field)), Some(info.data)]);
+        for child in info.children {
+            line.add_child(to_line(child));
+        }
+        line
     }
 
-    // Recurses down the tree of entries and find the one with the "widest" field name (taking into account tree depth)
-    fn get_max_field_width(info: &CpuInfo, depth: usize) -> usize {
-        let max_child_width = info
-            .children
-            .iter()
-            .map(|entry| get_max_field_width(entry, depth + 1))
-            .max()
-            .unwrap_or_default();
-
-        let own_width = indentation(depth) + info.field.len();
-        cmp::max(own_width, max_child_width)
-    }
-
-    fn print_entries(
-        entries: &Vec<CpuInfo>,
-        depth: usize,
-        max_field_width: usize,
-        _out_opts: &OutputOptions,
-    ) {
-        for entry in entries {
-            let margin = indentation(depth);
-            let padding = cmp::max(max_field_width - margin - entry.field.len(), 0);
-            println!(
-                "{}{}:{} {}",
-                " ".repeat(margin),
-                entry.field,
-                " ".repeat(padding),
-                entry.data
-            );
-            print_entries(&entry.children, depth + 1, max_field_width, _out_opts);
-        }
+    // The data of all entries is aligned to the same column, past the widest (indented) field name
+    let mut table = smartcols::Table::new("lscpu");
+    table.add_column(smartcols::Column::new("FIELD").tree(true));
+    table.add_column(smartcols::Column::new("DATA"));
+    table.set_noheadings(true);
+    // Subsections are only shown by indentation, 2 spaces per level
+    table.set_tree_symbols(smartcols::TreeSymbols {
+        branch: "  ".to_string(),
+        vertical: "  ".to_string(),
+        right: "  ".to_string(),
+    });
+    for info in infos.lscpu {
+        table.add_line(to_line(info));
     }
+    table.print();
+}
 
-    // Used to align all values to the same column
-    let max_field_width = infos
-        .lscpu
+// Frequency columns are only shown by default if cpufreq information is actually available
+fn default_parse_columns() -> Vec<Column> {
+    vec![
+        Column::Cpu,
+        Column::Core,
+        Column::Socket,
+        Column::Node,
+        Column::Cache,
+    ]
+}
+
+fn default_extended_columns(cpu_topology: &sysfs::CpuTopology) -> Vec<Column> {
+    let mut columns = vec![Column::Cpu];
+
+    // Levels that most systems only have one of per socket are left out unless they tell something apart
+    let sockets = cpu_topology.socket_count().unwrap_or(1);
+    let has_many = |count: Option<usize>, than: usize| count.is_some_and(|count| count > than);
+
+    if has_many(cpu_topology.drawer_count(), 1) {
+        columns.push(Column::Drawer);
+    }
+    if has_many(cpu_topology.book_count(), 1) {
+        columns.push(Column::Book);
+    }
+    columns.push(Column::Socket);
+    if has_many(cpu_topology.die_count(), sockets) {
+        columns.push(Column::Die);
+    }
+    if has_many(cpu_topology.cluster_count(), sockets) {
+        columns.push(Column::Cluster);
+    }
+    columns.push(Column::Core);
+
+    if cpu_topology
+        .cpus
         .iter()
-        .map(|info| get_max_field_width(info, 0))
-        .max()
-        .unwrap();
+        .any(|cpu| cpu.freq.max_mhz.is_some())
+    {
+        columns.extend([Column::MaxMhz, Column::MinMhz]);
+    }
+    if cpu_topology
+        .cpus
+        .iter()
+        .any(|cpu| cpu.freq.cur_mhz.is_some())
+    {
+        columns.push(Column::Mhz);
+    }
+
+    columns
+}
+
+fn print_extended(cpu_topology: &sysfs::CpuTopology, columns: &[Column], out_opts: &OutputOptions) {
+    let caches = group_caches(&cpu_topology.cpus);
+    let mut table = new_table("cpus", out_opts);
+    for col in columns {
+        table.add_column(
+            smartcols::Column::new(&col.get_header(&caches))
+                .right(true)
+                .json_type(col.get_json_type())
+                .placeholder("-"),
+        );
+    }
+    for cpu in &cpu_topology.cpus {
+        table.add_line(smartcols::Line::new(
+            columns.iter().map(|col| col.get_value(cpu, &caches)),
+        ));
+    }
+    table.print();
+}
+
+// Without a list of columns, the header and the cache fields are kept as older versions printed them,
+// with mixed-case names and one field per cache after an empty one
+fn print_parse(cpu_topology: &sysfs::CpuTopology, columns: &[Column], compat: bool) {
+    println!("# The following is the parsable format, which can be fed to other");
+    println!("# programs. Each different item in every column has an unique ID");
+    println!("# starting usually from zero.");
 
-    print_entries(&infos.lscpu, 0, max_field_width, &out_opts);
+    let caches = group_caches(&cpu_topology.cpus);
+    let mut headers: Vec<String> = vec![];
+    for col in columns {
+        match col {
+            Column::Cache if compat => {
+                if !caches.is_empty() {
+                    headers.push(String::new());
+                    headers.extend(caches.keys().cloned());
+                }
+            }
+            Column::Core if compat => headers.push("Core".to_string()),
+            Column::Socket if compat => headers.push("Socket".to_string()),
+            Column::Node if compat => headers.push("Node".to_string()),
+            _ => headers.push(col.get_header(&caches)),
+        }
+    }
+    println!("# {}", headers.join(","));
+
+    for cpu in &cpu_topology.cpus {
+        let mut values: Vec<String> = vec![];
+        for col in columns {
+            match col {
+                Column::Cache if compat => {
+                    if !caches.is_empty() {
+                        values.push(String::new());
+                        values.extend(cache_ids(cpu, &caches));
+                    }
+                }
+                _ => values.push(col.get_value(cpu, &caches).unwrap_or_default()),
+            }
+        }
+        println!("{}", values.join(","));
+    }
+}
+
+fn print_caches(
+    cpu_topology: &sysfs::CpuTopology,
+    columns: &[CacheColumn],
+    out_opts: &OutputOptions,
+) {
+    let cache_rows: Vec<_> = group_caches(&cpu_topology.cpus)
+        .into_iter()
+        .map(|(name, caches)| {
+            // All instances of the same cache are expected to share the same geometry
+            let first = caches[0];
+            CacheRow {
+                name,
+                one_size: first.size.size_bytes(),
+                all_size: caches.iter().map(|c| c.size.size_bytes()).sum(),
+                ways: first.ways,
+                typ: first.typ.to_string(),
+                level: first.level,
+                sets: first.sets,
+                phy_line: first.physical_line_partition,
+                coherency_size: first.coherency_line_size,
+            }
+        })
+        .collect();
+
+    let mut table = new_table("caches", out_opts);
+    for col in columns {
+        table.add_column(
+            smartcols::Column::new(col.get_name())
+                .right(col.is_right_aligned())
+                .json_type(col.get_json_type(out_opts.bytes))
+                .placeholder("-"),
+        );
+    }
+    for row in &cache_rows {
+        table.add_line(smartcols::Line::new(
+            columns.iter().map(|col| col.get_value(row, out_opts.bytes)),
+        ));
+    }
+    table.print();
+}
+
+fn new_table(name: &str, out_opts: &OutputOptions) -> smartcols::Table {
+    let mut table = smartcols::Table::new(name);
+    if out_opts.json {
+        table.set_format(smartcols::OutputFormat::Json);
+    }
+    table
+}
+
+fn format_mhz(mhz: f64) -> String {
+    format!("{:.4}", mhz)
+}
+
+fn format_percent(percent: f64) -> String {
+    format!("{:.0}%", percent)
 }
 
 fn find_cpuinfo_value(contents: &str, key: &str) -> Option<String> {
-    let pattern = format!(r"^{}\s+:\s+(.*)$", key);
+    let pattern = format!(r"^{}\s+:\s+(.*)$", regex::escape(key));
     let re = RegexBuilder::new(pattern.as_str())
         .multi_line(true)
         .build()
@@ -298,18 +1015,98 @@ fn find_cpuinfo_value(contents: &str, key: &str) -> Option<String> {
     value
 }
 
-// TODO: This is non-exhaustive and assumes that compile-time arch is the same as runtime
-// This is not always guaranteed to be the case, ie. you can run a x86 binary on a x86_64 machine
-fn get_architecture() -> String {
-    if cfg!(target_arch = "x86") {
-        "x86".to_string()
-    } else if cfg!(target_arch = "x86_64") {
-        "x86_64".to_string()
+// The kernel exposes the same machine name as `uname(2)` in procfs, which also works for another system root
+// If it's missing there, the name is taken from the running kernel, and kept in the snapshot for `--dump`
+fn get_architecture(sysroot: &sysfs::SysRoot) -> String {
+    const PATH_ARCH: &str = "/proc/sys/kernel/arch";
+
+    if let Some(arch) = sysroot.read_value::<String>(PATH_ARCH) {
+        return arch;
+    }
+
+    // The running machine says nothing about another system root, whose architecture is then unknown
+    if !sysroot.is_live() {
+        return String::new();
+    }
+
+    let arch = get_machine_name();
+    sysroot.add_to_snapshot(PATH_ARCH, Some(format!("{}\n", arch).as_bytes()));
+    arch
+}
+
+// Uses the machine name reported by the running kernel rather than the compile-time target,
+// so that ie. a x86 binary running on a x86_64 machine still reports the actual architecture
+#[cfg(unix)]
+fn get_machine_name() -> String {
+    use std::ffi::CStr;
+
+    let mut uts: libc::utsname = unsafe { std::mem::zeroed() };
+    if unsafe { libc::uname(&mut uts) } != 0 {
+        return std::env::consts::ARCH.to_string();
+    }
+
+    unsafe { CStr::from_ptr(uts.machine.as_ptr()) }
+        .to_string_lossy()
+        .into_owned()
+}
+
+#[cfg(not(unix))]
+fn get_machine_name() -> String {
+    std::env::consts::ARCH.to_string()
+}
+
+// Determines which of the 32-bit and 64-bit modes the CPU is able to run in
+// Mirrors util-linux, which uses cpuinfo flags where the architecture exposes them and falls back to known defaults otherwise
+// Whether AArch32 is supported can only be probed on the running system, so it's assumed unsupported otherwise
+fn get_op_modes(architecture: &str, cpuinfo: &str, is_live: bool) -> Option<String> {
+    let has_flag = |key: &str, flag: &str| {
+        find_cpuinfo_value(cpuinfo, key)
+            .map(|flags| flags.split_whitespace().any(|f| f == flag))
+            .unwrap_or(false)
+    };
+
+    let (bit32, bit64) = match architecture {
+        "x86_64" | "i386" | "i486" | "i586" | "i686" => (true, has_flag("flags", "lm")),
+        "s390x" => (true, true),
+        "s390" => (true, has_flag("features", "zarch")),
+        "aarch64" | "arm64" => (is_live && supports_aarch32(), true),
+        "ppc64" | "ppc64le" => (true, true),
+        "riscv64" | "loongarch64" => (false, true),
+        _ => return None,
+    };
+
+    let modes: Vec<_> = [(bit32, "32-bit"), (bit64, "64-bit")]
+        .iter()
+        .filter(|(supported, _)| *supported)
+        .map(|(_, mode)| *mode)
+        .collect();
+
+    if modes.is_empty() {
+        None
     } else {
-        "Unknown".to_string()
+        Some(modes.join(", "))
     }
 }
 
+// Switching to the PER_LINUX32 personality only succeeds if the kernel can run AArch32 code
+#[cfg(target_os = "linux")]
+fn supports_aarch32() -> bool {
+    const PER_LINUX32: libc::c_ulong = 0x0008;
+
+    let previous = unsafe { libc::personality(PER_LINUX32) };
+    if previous == -1 {
+        return false;
+    }
+
+    unsafe { libc::personality(previous as libc::c_ulong) };
+    true
+}
+
+#[cfg(not(target_os = "linux"))]
+fn supports_aarch32() -> bool {
+    false
+}
+
 pub fn uu_app() -> Command {
     Command::new(uucore::util_name())
         .version(crate_version!())
@@ -339,6 +1136,105 @@ pub fn uu_app() -> Command {
                 )
                 .action(ArgAction::SetTrue),
         )
+        .arg(
+            Arg::new(options::EXTENDED)
+                .short('e')
+                .long("extended")
+                .help(
+                    "Display the CPU information in human-readable format. \
+                    If the list argument is omitted, the default columns are used.",
+                )
+                .ignore_case(true)
+                .action(ArgAction::Set)
+                .value_name("list")
+                .value_delimiter(',')
+                .value_parser(EnumValueParser::<Column>::new())
+                .num_args(0..=1)
+                .require_equals(true),
+        )
+        .arg(
+            Arg::new(options::PARSE)
+                .short('p')
+                .long("parse")
+                .help(
+                    "Optimize the command output for easy parsing. \
+                    If the list argument is omitted, the default columns are used.",
+                )
+                .ignore_case(true)
+                .action(ArgAction::Set)
+                .value_name("list")
+                .value_delimiter(',')
+                .value_parser(EnumValueParser::<Column>::new())
+                .num_args(0..=1)
+                .require_equals(true)
+                .conflicts_with_all([options::EXTENDED, options::CACHES]),
+        )
+        .arg(
+            Arg::new(options::CACHES)
+                .short('C')
+                .long("caches")
+                .help(
+                    "Display details about CPU caches. \
+                    If the list argument is omitted, all columns are used.",
+                )
+                .ignore_case(true)
+                .action(ArgAction::Set)
+                .value_name("list")
+                .value_delimiter(',')
+                .value_parser(EnumValueParser::<CacheColumn>::new())
+                .num_args(0..=1)
+                .require_equals(true)
+                .conflicts_with(options::EXTENDED),
+        )
+        .arg(
+            Arg::new(options::FLAGS)
+                .long("flags")
+                .help(
+                    "Display the CPU flags grouped by category (SIMD, crypto, virtualization, \
+                    security), along with the x86-64 ISA level they correspond to.",
+                )
+                .action(ArgAction::SetTrue)
+                .conflicts_with_all([options::EXTENDED, options::PARSE, options::CACHES]),
+        )
+        .arg(
+            Arg::new(options::HIERARCHIC)
+                .long("hierarchic")
+                .help("Use subsections in summary output.")
+                .action(ArgAction::Set)
+                .value_name("when")
+                .value_parser(EnumValueParser::<Hierarchic>::new())
+                .num_args(0..=1)
+                .require_equals(true)
+                .default_missing_value("always"),
+        )
+        .arg(
+            Arg::new(options::EXIT_ON_VULNERABLE)
+                .long("exit-on-vulnerable")
+                .help(format!(
+                    "Exit with status {} if the CPU is affected by any vulnerability \
+                    that isn't mitigated, or whose status is unknown.",
+                    EXIT_VULNERABLE
+                ))
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::SYSROOT)
+                .short('s')
+                .long("sysroot")
+                .help("Gather CPU data for a Linux instance other than the instance from which lscpu is issued.")
+                .action(ArgAction::Set)
+                .value_name("dir"),
+        )
+        .arg(
+            Arg::new(options::DUMP)
+                .long("dump")
+                .help(
+                    "Copy every file lscpu reads into the directory, or into a tarball if it \
+                    ends with .tar, so that the output can be reproduced with --sysroot.",
+                )
+                .action(ArgAction::Set)
+                .value_name("dir"),
+        )
         .arg(
             Arg::new(options::BYTES)
                 .short('B')
@@ -350,4 +1246,37 @@ pub fn uu_app() -> Command {
                     Setting this flag instead prints the decimal amount of bytes with no suffix.",
                 ),
         )
+        .after_help(format!(
+            "Available output columns for -e or -p:\n{}\n\nAvailable output columns for -C:\n{}",
+            Column::value_variants()
+                .iter()
+                .map(|col| format!("{:>15}  {}", col.get_name(), col.get_help()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            CacheColumn::value_variants()
+                .iter()
+                .map(|col| format!("{:>15}  {}", col.get_name(), col.get_help()))
+                .collect::<Vec<_>>()
+                .join("\n")
+        ))
+}
+
+#[test]
+fn test_get_op_modes() {
+    let x86_64 = "flags\t\t: fpu vme de pse tsc msr pae mce cx8 lm constant_tsc\n";
+    let i686 = "flags\t\t: fpu vme de pse tsc msr pae mce cx8\n";
+    let s390 = "features\t: esan3 zarch stfle msa ldisp eimm dfp\n";
+
+    assert_eq!(
+        get_op_modes("x86_64", x86_64, true).unwrap(),
+        "32-bit, 64-bit"
+    );
+    assert_eq!(get_op_modes("i686", i686, true).unwrap(), "32-bit");
+    assert_eq!(get_op_modes("s390", s390, true).unwrap(), "32-bit, 64-bit");
+    assert_eq!(get_op_modes("s390x", "", true).unwrap(), "32-bit, 64-bit");
+    assert_eq!(get_op_modes("ppc64le", "", true).unwrap(), "32-bit, 64-bit");
+    assert_eq!(get_op_modes("riscv64", "", true).unwrap(), "64-bit");
+    assert_eq!(get_op_modes("loongarch64", "", true).unwrap(), "64-bit");
+    assert_eq!(get_op_modes("aarch64", "", false).unwrap(), "64-bit");
+    assert_eq!(get_op_modes("sparc", "", true), None);
 }
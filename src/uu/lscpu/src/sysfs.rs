@@ -3,23 +3,162 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-use std::{collections::HashSet, fs, path::PathBuf};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt, fs,
+    hash::Hash,
+    io,
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+use uucore::error::{FromIo, UResult, USimpleError};
+
+pub const PATH_SYS_CPU: &str = "sys/devices/system/cpu";
+const PATH_SYS_NODE: &str = "sys/devices/system/node";
+
+// Files and directories read through a `SysRoot`, keyed by their path relative to the root
+// Directories have no content, so that they can be recreated even when none of their files were read
+pub type Snapshot = BTreeMap<PathBuf, Option<Vec<u8>>>;
+
+// All paths read by lscpu are resolved relative to this root, which is `/` unless `--sysroot` points it elsewhere
+pub struct SysRoot {
+    root: PathBuf,
+    snapshot: Option<RefCell<Snapshot>>,
+}
+
+impl SysRoot {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self {
+            root: root.into(),
+            snapshot: None,
+        }
+    }
+
+    // Keeps a copy of everything read from now on, see `take_snapshot`
+    pub fn record(mut self) -> Self {
+        self.snapshot = Some(RefCell::new(Snapshot::new()));
+        self
+    }
+
+    // Whether this is the root of the running system, rather than a snapshot of another one
+    // Information that doesn't come from files (ie. `uname(2)` or the auxv) is only meaningful in that case
+    pub fn is_live(&self) -> bool {
+        self.root == Path::new("/")
+    }
+
+    pub fn path(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        self.root.join(path.strip_prefix("/").unwrap_or(path))
+    }
+
+    pub fn read_to_string(&self, path: impl AsRef<Path>) -> io::Result<String> {
+        let content = fs::read_to_string(self.path(&path))?;
+        self.add_to_snapshot(path, Some(content.as_bytes()));
+        Ok(content)
+    }
+
+    // Reads raw bytes, for files that may hold private data and are therefore not part of a snapshot as they are
+    // Callers add back what can safely be kept with `add_to_snapshot`
+    pub fn read_private(&self, path: impl AsRef<Path>) -> io::Result<Vec<u8>> {
+        fs::read(self.path(path))
+    }
+
+    pub fn read_dir(&self, path: impl AsRef<Path>) -> io::Result<fs::ReadDir> {
+        let dir = fs::read_dir(self.path(&path))?;
+        self.add_to_snapshot(path, None);
+        Ok(dir)
+    }
+
+    pub fn exists(&self, path: impl AsRef<Path>) -> bool {
+        let full_path = self.path(&path);
+        if full_path.is_dir() {
+            self.add_to_snapshot(path, None);
+        } else if let Ok(content) = fs::read(&full_path) {
+            self.add_to_snapshot(path, Some(&content));
+        }
+        full_path.exists()
+    }
+
+    // Reads a single trimmed value, treating a missing file and an unparsable value alike
+    pub fn read_value<T: FromStr>(&self, path: impl AsRef<Path>) -> Option<T> {
+        self.read_to_string(path)
+            .ok()
+            .and_then(|content| content.trim().parse::<T>().ok())
+    }
+
+    // Adds a value that didn't come from a file, so that replaying the snapshot gives the same result
+    pub fn add_to_snapshot(&self, path: impl AsRef<Path>, content: Option<&[u8]>) {
+        if let Some(snapshot) = &self.snapshot {
+            let path = path.as_ref();
+            snapshot.borrow_mut().insert(
+                path.strip_prefix("/").unwrap_or(path).to_path_buf(),
+                content.map(|content| content.to_vec()),
+            );
+        }
+    }
+
+    pub fn take_snapshot(&self) -> Snapshot {
+        self.snapshot
+            .as_ref()
+            .map(|snapshot| snapshot.take())
+            .unwrap_or_default()
+    }
+}
 
 pub struct CpuVulnerability {
     pub name: String,
-    pub mitigation: String,
+    pub status: VulnerabilityStatus,
+    // Whatever the kernel reports beyond the status itself, ie. which mitigation is in use
+    pub details: Option<String>,
+    // The kernel's description, as printed by util-linux
+    pub description: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum VulnerabilityStatus {
+    NotAffected,
+    Vulnerable,
+    Mitigation,
+    Unknown,
+}
+
+impl VulnerabilityStatus {
+    // A status that isn't known can't be shown to be mitigated either, so checks err on the side of failing
+    pub fn is_unmitigated(&self) -> bool {
+        matches!(self, Self::Vulnerable | Self::Unknown)
+    }
 }
 
 pub struct CpuTopology {
     pub cpus: Vec<Cpu>,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Default)]
 pub struct Cpu {
-    _index: usize,
-    pub pkg_id: usize,
-    pub core_id: usize,
+    pub index: usize,
+    pub pkg_id: Option<usize>,
+    pub core_id: Option<usize>,
+    pub die_id: Option<usize>,
+    pub cluster_id: Option<usize>,
+    // Books and drawers are additional topology levels above sockets, only found on s390
+    pub book_id: Option<usize>,
+    pub drawer_id: Option<usize>,
+    pub node_id: Option<usize>,
+    // The CPUs sharing the same core, package or die as this one, in the kernel's list format
+    pub core_cpus_list: Option<String>,
+    pub package_cpus_list: Option<String>,
+    pub die_cpus_list: Option<String>,
     pub caches: Vec<CpuCache>,
+    pub freq: CpuFreq,
+}
+
+// Frequencies are exposed by cpufreq in kHz, but lscpu reports everything in MHz
+#[derive(Debug, Default)]
+pub struct CpuFreq {
+    pub min_mhz: Option<f64>,
+    pub max_mhz: Option<f64>,
+    pub cur_mhz: Option<f64>,
 }
 
 #[derive(Debug)]
@@ -28,6 +167,10 @@ pub struct CpuCache {
     pub level: usize,
     pub size: CacheSize,
     pub shared_cpu_map: String,
+    pub ways: Option<u64>,
+    pub sets: Option<u64>,
+    pub physical_line_partition: Option<u64>,
+    pub coherency_line_size: Option<u64>,
 }
 
 #[derive(Debug)]
@@ -41,49 +184,186 @@ pub enum CacheType {
 }
 
 impl CpuTopology {
-    pub fn new() -> Self {
+    pub fn new(sysroot: &SysRoot) -> UResult<Self> {
         let mut out: Vec<Cpu> = vec![];
 
-        let online_cpus = parse_cpu_list(&read_online_cpus());
+        let online_list = read_online_cpus(sysroot)?;
+        let online_cpus = parse_cpu_list(&online_list).ok_or_else(|| {
+            USimpleError::new(1, format!("invalid list of online CPUs: '{}'", online_list))
+        })?;
+        let nodes = read_cpu_nodes(sysroot);
 
         for cpu_index in online_cpus {
-            let cpu_dir = PathBuf::from(format!("/sys/devices/system/cpu/cpu{}/", cpu_index));
+            let cpu_dir = Path::new(PATH_SYS_CPU).join(format!("cpu{}", cpu_index));
 
-            let pkg_id = fs::read_to_string(cpu_dir.join("topology/physical_package_id"))
-                .unwrap()
-                .trim()
-                .parse::<usize>()
-                .unwrap();
-
-            let core_id = fs::read_to_string(cpu_dir.join("topology/core_id"))
-                .unwrap()
-                .trim()
-                .parse::<usize>()
-                .unwrap();
-
-            let caches = read_cpu_caches(cpu_index);
+            // Some platforms and sandboxes don't expose the topology at all, so these are optional
+            // IDs the kernel doesn't know about are reported as -1, which fails to parse and is left out as well
+            let topology_dir = cpu_dir.join("topology");
+            let read_id = |name: &str| sysroot.read_value(topology_dir.join(name));
+            let read_list = |name: &str| sysroot.read_value(topology_dir.join(name));
 
             out.push(Cpu {
-                _index: cpu_index,
-                pkg_id,
-                core_id,
-                caches,
+                index: cpu_index,
+                pkg_id: read_id("physical_package_id"),
+                core_id: read_id("core_id"),
+                die_id: read_id("die_id"),
+                cluster_id: read_id("cluster_id"),
+                book_id: read_id("book_id"),
+                drawer_id: read_id("drawer_id"),
+                node_id: nodes.get(&cpu_index).copied(),
+                core_cpus_list: read_list("core_cpus_list"),
+                package_cpus_list: read_list("package_cpus_list"),
+                die_cpus_list: read_list("die_cpus_list"),
+                caches: read_cpu_caches(sysroot, &cpu_dir),
+                freq: read_cpu_freq(sysroot, &cpu_dir),
             })
         }
-        Self { cpus: out }
+        Ok(Self { cpus: out })
+    }
+
+    fn count_unique<T: Eq + Hash>(&self, key: impl Fn(&Cpu) -> Option<T>) -> Option<usize> {
+        let unique: HashSet<_> = self.cpus.iter().filter_map(key).collect();
+        Some(unique.len()).filter(|count| *count > 0)
     }
 
-    pub fn socket_count(&self) -> usize {
+    // Prefers the kernel's list of sibling CPUs, as IDs are only unique within the level above them
+    fn count_siblings(
+        &self,
+        cpus_list: impl Fn(&Cpu) -> Option<String>,
+        key: impl Fn(&Cpu) -> Option<(Option<usize>, usize)>,
+    ) -> Option<usize> {
+        if self.cpus.iter().any(|cpu| cpus_list(cpu).is_some()) {
+            self.count_unique(cpus_list)
+        } else {
+            self.count_unique(key)
+        }
+    }
+
+    pub fn socket_count(&self) -> Option<usize> {
         // Each physical socket is represented as its own package_id, so amount of unique pkg_ids = sockets
         // https://www.kernel.org/doc/html/latest/admin-guide/abi-stable.html#abi-sys-devices-system-cpu-cpux-topology-physical-package-id
-        let physical_sockets: HashSet<_> = self.cpus.iter().map(|cpu| cpu.pkg_id).collect();
+        self.count_siblings(
+            |cpu| cpu.package_cpus_list.clone(),
+            |cpu| cpu.pkg_id.map(|id| (None, id)),
+        )
+    }
+
+    pub fn core_count(&self) -> Option<usize> {
+        self.count_siblings(
+            |cpu| cpu.core_cpus_list.clone(),
+            |cpu| cpu.core_id.map(|id| (cpu.pkg_id, id)),
+        )
+    }
 
-        physical_sockets.len()
+    pub fn die_count(&self) -> Option<usize> {
+        self.count_siblings(
+            |cpu| cpu.die_cpus_list.clone(),
+            |cpu| cpu.die_id.map(|id| (cpu.pkg_id, id)),
+        )
     }
 
-    pub fn core_count(&self) -> usize {
-        let core_ids: HashSet<_> = self.cpus.iter().map(|cpu| cpu.core_id).collect();
-        core_ids.len()
+    pub fn cluster_count(&self) -> Option<usize> {
+        self.count_unique(|cpu| cpu.cluster_id.map(|id| (cpu.pkg_id, id)))
+    }
+
+    pub fn book_count(&self) -> Option<usize> {
+        self.count_unique(|cpu| cpu.book_id.map(|id| (cpu.drawer_id, id)))
+    }
+
+    pub fn drawer_count(&self) -> Option<usize> {
+        self.count_unique(|cpu| cpu.drawer_id)
+    }
+
+    pub fn max_freq_mhz(&self) -> Option<f64> {
+        self.cpus
+            .iter()
+            .filter_map(|cpu| cpu.freq.max_mhz)
+            .reduce(f64::max)
+    }
+
+    pub fn min_freq_mhz(&self) -> Option<f64> {
+        self.cpus
+            .iter()
+            .filter_map(|cpu| cpu.freq.min_mhz)
+            .reduce(f64::min)
+    }
+
+    // Average of how far each CPU is currently scaled relative to its own maximum frequency
+    pub fn freq_scaling_percent(&self) -> Option<f64> {
+        let percents: Vec<_> = self
+            .cpus
+            .iter()
+            .filter_map(|cpu| cpu.freq.scaling_percent())
+            .collect();
+
+        if percents.is_empty() {
+            return None;
+        }
+        Some(percents.iter().sum::<f64>() / percents.len() as f64)
+    }
+}
+
+impl CpuCache {
+    // Caches are named by level, with a suffix for split data/instruction caches, ie. `L1d`, `L1i` or `L2`
+    pub fn name(&self) -> String {
+        let type_suffix = match self.typ {
+            CacheType::Instruction => "i",
+            CacheType::Data => "d",
+            CacheType::Unified => "",
+        };
+        format!("L{}{}", self.level, type_suffix)
+    }
+}
+
+impl fmt::Display for CacheType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CacheType::Data => write!(f, "Data"),
+            CacheType::Instruction => write!(f, "Instruction"),
+            CacheType::Unified => write!(f, "Unified"),
+        }
+    }
+}
+
+impl CpuVulnerability {
+    fn parse(name: &str, content: &str) -> Self {
+        let content = content.trim();
+        let (status, details) = parse_vulnerability_status(content);
+
+        // File names come from the snapshot with `--sysroot`, so they may be empty or start with any character
+        let mut chars = name.chars();
+        let name: String = chars
+            .next()
+            .map(|first| first.to_uppercase().chain(chars).collect())
+            .unwrap_or_default();
+
+        Self {
+            name: name.replace("_", " "),
+            status,
+            details,
+            // The kernel is inconsistent in its use of ": " and "; ", util-linux settles on the latter
+            description: content.replacen("Mitigation: ", "Mitigation; ", 1),
+        }
+    }
+}
+
+impl fmt::Display for VulnerabilityStatus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VulnerabilityStatus::NotAffected => write!(f, "Not affected"),
+            VulnerabilityStatus::Vulnerable => write!(f, "Vulnerable"),
+            VulnerabilityStatus::Mitigation => write!(f, "Mitigation"),
+            VulnerabilityStatus::Unknown => write!(f, "Unknown"),
+        }
+    }
+}
+
+impl CpuFreq {
+    pub fn scaling_percent(&self) -> Option<f64> {
+        match (self.cur_mhz, self.max_mhz) {
+            (Some(cur), Some(max)) if max > 0.0 => Some(cur / max * 100.0),
+            _ => None,
+        }
     }
 }
 
@@ -92,26 +372,25 @@ impl CacheSize {
         Self(size)
     }
 
-    fn parse(s: &str) -> Self {
+    fn parse(s: &str) -> Option<Self> {
         // Yes, this will break if we ever reach a point where caches exceed terabytes in size...
         const EXPONENTS: [(char, u32); 4] = [('K', 1), ('M', 2), ('G', 3), ('T', 4)];
 
         // If we only have numbers, treat it as a raw amount of bytes and parse as-is
         if s.chars().all(char::is_numeric) {
-            return Self(s.parse::<u64>().expect("Could not parse cache size"));
+            return s.parse::<u64>().ok().map(Self);
         };
 
         for (suffix, exponent) in EXPONENTS {
-            if s.ends_with(suffix) {
-                let nums = s.strip_suffix(suffix).unwrap();
-                let value = nums.parse::<u64>().expect("Could not parse cache size");
+            if let Some(nums) = s.strip_suffix(suffix) {
+                let value = nums.parse::<u64>().ok()?;
                 let multiplier = 1024_u64.pow(exponent);
 
-                return Self(value * multiplier);
+                return value.checked_mul(multiplier).map(Self);
             }
         }
 
-        panic!("No known suffix in cache size string");
+        None
     }
 
     pub fn size_bytes(&self) -> u64 {
@@ -137,82 +416,128 @@ impl CacheSize {
 }
 
 // TODO: respect `--hex` option and output the bitmask instead of human-readable range
-pub fn read_online_cpus() -> String {
-    fs::read_to_string("/sys/devices/system/cpu/online")
-        .expect("Could not read sysfs")
-        .trim()
-        .to_string()
+pub fn read_online_cpus(sysroot: &SysRoot) -> UResult<String> {
+    let path = Path::new(PATH_SYS_CPU).join("online");
+    sysroot
+        .read_to_string(&path)
+        .map(|content| content.trim().to_string())
+        .map_err_context(|| format!("cannot read {}", sysroot.path(&path).display()))
 }
 
-fn read_cpu_caches(cpu_index: usize) -> Vec<CpuCache> {
-    let cpu_dir = PathBuf::from(format!("/sys/devices/system/cpu/cpu{}/", cpu_index));
-    let cache_dir = fs::read_dir(cpu_dir.join("cache")).unwrap();
-    let cache_paths = cache_dir
-        .flatten()
-        .filter(|x| x.path().is_dir())
-        .map(|x| x.path());
+// Maps each CPU to its NUMA node, through the CPU lists of the nodes rather than the `nodeN` links
+// in the CPU directories, which a snapshot can't keep
+fn read_cpu_nodes(sysroot: &SysRoot) -> HashMap<usize, usize> {
+    let Ok(node_dir) = sysroot.read_dir(PATH_SYS_NODE) else {
+        return HashMap::new();
+    };
 
-    let mut caches: Vec<CpuCache> = vec![];
+    let mut nodes = HashMap::new();
+    for entry in node_dir.flatten() {
+        let file_name = entry.file_name();
+        let Some(node) = file_name
+            .to_str()
+            .and_then(|name| name.strip_prefix("node"))
+            .and_then(|index| index.parse::<usize>().ok())
+        else {
+            continue;
+        };
+        let cpus = sysroot
+            .read_to_string(Path::new(PATH_SYS_NODE).join(&file_name).join("cpulist"))
+            .ok()
+            .and_then(|list| parse_cpu_list(&list));
+        for cpu in cpus.unwrap_or_default() {
+            nodes.insert(cpu, node);
+        }
+    }
+    nodes
+}
 
-    for cache_path in cache_paths {
-        let type_string = fs::read_to_string(cache_path.join("type")).unwrap();
+// Caches that are missing or can't be fully parsed are left out, rather than failing the whole CPU
+fn read_cpu_caches(sysroot: &SysRoot, cpu_dir: &Path) -> Vec<CpuCache> {
+    let Ok(cache_dir) = sysroot.read_dir(cpu_dir.join("cache")) else {
+        return vec![];
+    };
 
-        let c_type = match type_string.trim() {
-            "Unified" => CacheType::Unified,
-            "Data" => CacheType::Data,
-            "Instruction" => CacheType::Instruction,
-            _ => panic!("Unrecognized cache type: {}", type_string),
-        };
+    let mut cache_paths: Vec<_> = cache_dir
+        .flatten()
+        .filter(|x| x.path().is_dir())
+        .map(|x| cpu_dir.join("cache").join(x.file_name()))
+        .collect();
+    cache_paths.sort();
+
+    cache_paths
+        .iter()
+        .filter_map(|cache_path| read_cpu_cache(sysroot, cache_path))
+        .collect()
+}
 
-        let c_level = fs::read_to_string(cache_path.join("level"))
-            .map(|s| s.trim().parse::<usize>().unwrap())
-            .unwrap();
+fn read_cpu_cache(sysroot: &SysRoot, cache_path: &Path) -> Option<CpuCache> {
+    let typ = match sysroot
+        .read_value::<String>(cache_path.join("type"))?
+        .as_str()
+    {
+        "Unified" => CacheType::Unified,
+        "Data" => CacheType::Data,
+        "Instruction" => CacheType::Instruction,
+        _ => return None,
+    };
 
-        let size_string = fs::read_to_string(cache_path.join("size")).unwrap();
-        let c_size = CacheSize::parse(size_string.trim());
+    let level = sysroot.read_value(cache_path.join("level"))?;
+    let size = CacheSize::parse(&sysroot.read_value::<String>(cache_path.join("size"))?)?;
+    let shared_cpu_map = sysroot.read_value(cache_path.join("shared_cpu_map"))?;
+
+    Some(CpuCache {
+        level,
+        size,
+        typ,
+        shared_cpu_map,
+        ways: sysroot.read_value(cache_path.join("ways_of_associativity")),
+        sets: sysroot.read_value(cache_path.join("number_of_sets")),
+        physical_line_partition: sysroot.read_value(cache_path.join("physical_line_partition")),
+        coherency_line_size: sysroot.read_value(cache_path.join("coherency_line_size")),
+    })
+}
 
-        let shared_cpu_map = fs::read_to_string(cache_path.join("shared_cpu_map"))
-            .unwrap()
-            .trim()
-            .to_string();
+fn read_cpu_freq(sysroot: &SysRoot, cpu_dir: &Path) -> CpuFreq {
+    let freq_dir = cpu_dir.join("cpufreq");
 
-        caches.push(CpuCache {
-            level: c_level,
-            size: c_size,
-            typ: c_type,
-            shared_cpu_map,
-        });
-    }
+    let read_mhz = |name: &str| {
+        sysroot
+            .read_value::<u64>(freq_dir.join(name))
+            .map(|khz| khz as f64 / 1000.0)
+    };
 
-    caches
+    CpuFreq {
+        min_mhz: read_mhz("cpuinfo_min_freq"),
+        max_mhz: read_mhz("cpuinfo_max_freq"),
+        cur_mhz: read_mhz("scaling_cur_freq"),
+    }
 }
 
-pub fn read_freq_boost_state() -> Option<bool> {
-    fs::read_to_string("/sys/devices/system/cpu/cpufreq/boost")
+pub fn read_freq_boost_state(sysroot: &SysRoot) -> Option<bool> {
+    sysroot
+        .read_to_string(Path::new(PATH_SYS_CPU).join("cpufreq/boost"))
         .map(|content| content.trim() == "1")
         .ok()
 }
 
-pub fn read_cpu_vulnerabilities() -> Vec<CpuVulnerability> {
+pub fn read_cpu_vulnerabilities(sysroot: &SysRoot) -> Vec<CpuVulnerability> {
     let mut out: Vec<CpuVulnerability> = vec![];
+    let vulns_dir = Path::new(PATH_SYS_CPU).join("vulnerabilities");
 
-    if let Ok(dir) = fs::read_dir("/sys/devices/system/cpu/vulnerabilities") {
+    if let Ok(dir) = sysroot.read_dir(&vulns_dir) {
         let mut files: Vec<_> = dir
             .flatten()
-            .map(|x| x.path())
-            .filter(|x| !x.is_dir())
+            .filter(|x| !x.path().is_dir())
+            .map(|x| x.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.is_empty())
             .collect();
 
-        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
-
-        for file in files {
-            if let Ok(content) = fs::read_to_string(&file) {
-                let name = file.file_name().unwrap().to_str().unwrap();
+        files.sort();
 
-                out.push(CpuVulnerability {
-                    name: (name[..1].to_uppercase() + &name[1..]).replace("_", " "),
-                    mitigation: content.trim().to_string(),
-                });
+        for name in files {
+            if let Ok(content) = sysroot.read_to_string(vulns_dir.join(&name)) {
+                out.push(CpuVulnerability::parse(&name, &content));
             }
         }
     };
@@ -220,8 +545,31 @@ pub fn read_cpu_vulnerabilities() -> Vec<CpuVulnerability> {
     out
 }
 
-pub fn read_cpu_byte_order() -> Option<&'static str> {
-    if let Ok(byte_order) = fs::read_to_string("/sys/kernel/cpu_byteorder") {
+// Splits a line from `vulnerabilities/*` into its status and the details following it
+// Some entries are prefixed by the component they apply to, ie. `KVM: Mitigation: VMX disabled`
+fn parse_vulnerability_status(content: &str) -> (VulnerabilityStatus, Option<String>) {
+    const STATUSES: [(&str, VulnerabilityStatus); 5] = [
+        ("Not affected", VulnerabilityStatus::NotAffected),
+        ("Mitigation", VulnerabilityStatus::Mitigation),
+        ("Vulnerable", VulnerabilityStatus::Vulnerable),
+        ("Processor vulnerable", VulnerabilityStatus::Vulnerable),
+        ("Unknown", VulnerabilityStatus::Unknown),
+    ];
+
+    let text = content.strip_prefix("KVM: ").unwrap_or(content);
+
+    for (prefix, status) in STATUSES {
+        if let Some(rest) = text.strip_prefix(prefix) {
+            let details = rest.trim_start_matches([':', ';']).trim();
+            return (status, Some(details.to_string()).filter(|d| !d.is_empty()));
+        }
+    }
+
+    (VulnerabilityStatus::Unknown, Some(text.to_string()))
+}
+
+pub fn read_cpu_byte_order(sysroot: &SysRoot) -> Option<&'static str> {
+    if let Ok(byte_order) = sysroot.read_to_string("/sys/kernel/cpu_byteorder") {
         match byte_order.trim() {
             "big" => return Some("Big Endian"),
             "little" => return Some("Little Endian"),
@@ -234,45 +582,55 @@ pub fn read_cpu_byte_order() -> Option<&'static str> {
 // Takes in a human-readable list of CPUs, and returns a list of indices parsed from that list
 // These can come in the form of a plain range like `X-Y`, or a comma-separated ranges and indices ie. `1,3-4,7-8,10`
 // Kernel docs with examples: https://www.kernel.org/doc/html/latest/admin-guide/cputopology.html
-fn parse_cpu_list(list: &str) -> Vec<usize> {
+fn parse_cpu_list(list: &str) -> Option<Vec<usize>> {
     let mut out: Vec<usize> = vec![];
 
     if list.is_empty() {
-        return out;
+        return Some(out);
     }
 
     for part in list.trim().split(",") {
-        if part.contains("-") {
-            let bounds: Vec<_> = part.split("-").flat_map(|x| x.parse::<usize>()).collect();
-            assert_eq!(bounds.len(), 2);
-            for idx in bounds[0]..bounds[1] + 1 {
-                out.push(idx)
+        if let Some((first, last)) = part.split_once("-") {
+            let first = first.parse::<usize>().ok()?;
+            let last = last.parse::<usize>().ok()?;
+            if first > last {
+                return None;
             }
+            out.extend(first..=last);
         } else {
-            let idx = part.parse::<usize>().expect("Invalid CPU index value");
-            out.push(idx);
+            out.push(part.parse::<usize>().ok()?);
         }
     }
 
-    out
+    Some(out)
 }
 
 #[test]
 fn test_parse_cache_size() {
-    assert_eq!(CacheSize::parse("512").size_bytes(), 512);
-    assert_eq!(CacheSize::parse("1K").size_bytes(), 1024);
-    assert_eq!(CacheSize::parse("1M").size_bytes(), 1024 * 1024);
-    assert_eq!(CacheSize::parse("1G").size_bytes(), 1024 * 1024 * 1024);
+    assert_eq!(CacheSize::parse("512").unwrap().size_bytes(), 512);
+    assert_eq!(CacheSize::parse("1K").unwrap().size_bytes(), 1024);
+    assert_eq!(CacheSize::parse("1M").unwrap().size_bytes(), 1024 * 1024);
     assert_eq!(
-        CacheSize::parse("1T").size_bytes(),
+        CacheSize::parse("1G").unwrap().size_bytes(),
+        1024 * 1024 * 1024
+    );
+    assert_eq!(
+        CacheSize::parse("1T").unwrap().size_bytes(),
         1024 * 1024 * 1024 * 1024
     );
-    assert_eq!(CacheSize::parse("123K").size_bytes(), 123 * 1024);
-    assert_eq!(CacheSize::parse("32M").size_bytes(), 32 * 1024 * 1024);
+    assert_eq!(CacheSize::parse("123K").unwrap().size_bytes(), 123 * 1024);
+    assert_eq!(
+        CacheSize::parse("32M").unwrap().size_bytes(),
+        32 * 1024 * 1024
+    );
     assert_eq!(
-        CacheSize::parse("345G").size_bytes(),
+        CacheSize::parse("345G").unwrap().size_bytes(),
         345 * 1024 * 1024 * 1024
     );
+
+    assert!(CacheSize::parse("").is_none());
+    assert!(CacheSize::parse("12X").is_none());
+    assert!(CacheSize::parse("K").is_none());
 }
 
 #[test]
@@ -293,17 +651,248 @@ fn test_print_cache_size() {
     );
 }
 
+#[test]
+fn test_freq_scaling_percent() {
+    let cpu = |cur_mhz, max_mhz| Cpu {
+        pkg_id: Some(0),
+        core_id: Some(0),
+        freq: CpuFreq {
+            min_mhz: Some(400.0),
+            max_mhz,
+            cur_mhz,
+        },
+        ..Default::default()
+    };
+
+    let topology = CpuTopology {
+        cpus: vec![
+            cpu(Some(1000.0), Some(4000.0)),
+            cpu(Some(3000.0), Some(4000.0)),
+            cpu(None, Some(4500.0)),
+        ],
+    };
+    assert_eq!(topology.freq_scaling_percent(), Some(50.0));
+    assert_eq!(topology.max_freq_mhz(), Some(4500.0));
+    assert_eq!(topology.min_freq_mhz(), Some(400.0));
+
+    let topology = CpuTopology {
+        cpus: vec![cpu(None, None)],
+    };
+    assert_eq!(topology.freq_scaling_percent(), None);
+    assert_eq!(topology.max_freq_mhz(), None);
+}
+
 #[test]
 fn test_parse_cpu_list() {
-    assert_eq!(parse_cpu_list(""), Vec::<usize>::new());
-    assert_eq!(parse_cpu_list("1-3"), Vec::<usize>::from([1, 2, 3]));
-    assert_eq!(parse_cpu_list("1,2,3"), Vec::<usize>::from([1, 2, 3]));
+    assert_eq!(parse_cpu_list(""), Some(Vec::<usize>::new()));
+    assert_eq!(parse_cpu_list("1-3"), Some(Vec::<usize>::from([1, 2, 3])));
+    assert_eq!(parse_cpu_list("1,2,3"), Some(Vec::<usize>::from([1, 2, 3])));
     assert_eq!(
         parse_cpu_list("1,3-6,8"),
-        Vec::<usize>::from([1, 3, 4, 5, 6, 8])
+        Some(Vec::<usize>::from([1, 3, 4, 5, 6, 8]))
     );
     assert_eq!(
         parse_cpu_list("1-2,3-5,7"),
-        Vec::<usize>::from([1, 2, 3, 4, 5, 7])
+        Some(Vec::<usize>::from([1, 2, 3, 4, 5, 7]))
+    );
+
+    assert_eq!(parse_cpu_list("3-1"), None);
+    assert_eq!(parse_cpu_list("1,a"), None);
+    assert_eq!(parse_cpu_list("1-"), None);
+}
+
+#[cfg(test)]
+fn write_sysfs_file(root: &Path, path: &str, content: &str) {
+    let path = root.join(path);
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+#[test]
+fn test_topology_without_topology_and_caches() {
+    // Roughly what gVisor and some containers expose: an online list and bare CPU directories
+    let root = tempfile::tempdir().unwrap();
+    write_sysfs_file(root.path(), "sys/devices/system/cpu/online", "0-1\n");
+    fs::create_dir_all(root.path().join("sys/devices/system/cpu/cpu0")).unwrap();
+    fs::create_dir_all(root.path().join("sys/devices/system/cpu/cpu1")).unwrap();
+
+    let topology = CpuTopology::new(&SysRoot::new(root.path())).unwrap();
+
+    assert_eq!(topology.cpus.len(), 2);
+    assert!(topology.cpus.iter().all(|cpu| cpu.pkg_id.is_none()));
+    assert!(topology.cpus.iter().all(|cpu| cpu.caches.is_empty()));
+    assert_eq!(topology.socket_count(), None);
+    assert_eq!(topology.core_count(), None);
+}
+
+#[test]
+fn test_topology_skips_unreadable_caches() {
+    let root = tempfile::tempdir().unwrap();
+    let cpu0 = "sys/devices/system/cpu/cpu0";
+    write_sysfs_file(root.path(), "sys/devices/system/cpu/online", "0\n");
+    write_sysfs_file(root.path(), &format!("{cpu0}/topology/core_id"), "0\n");
+    write_sysfs_file(
+        root.path(),
+        &format!("{cpu0}/topology/physical_package_id"),
+        "0\n",
+    );
+
+    for (index, typ, size) in [(0, "Data", "48K"), (1, "Weird", "32K"), (2, "Unified", "?")] {
+        let cache = format!("{cpu0}/cache/index{index}");
+        write_sysfs_file(root.path(), &format!("{cache}/type"), typ);
+        write_sysfs_file(root.path(), &format!("{cache}/level"), "1");
+        write_sysfs_file(root.path(), &format!("{cache}/size"), size);
+        write_sysfs_file(root.path(), &format!("{cache}/shared_cpu_map"), "1");
+    }
+
+    let topology = CpuTopology::new(&SysRoot::new(root.path())).unwrap();
+
+    assert_eq!(topology.socket_count(), Some(1));
+    assert_eq!(topology.core_count(), Some(1));
+    assert_eq!(topology.cpus[0].caches.len(), 1);
+    assert_eq!(topology.cpus[0].caches[0].size.size_bytes(), 48 * 1024);
+}
+
+#[test]
+fn test_topology_without_online_list() {
+    let root = tempfile::tempdir().unwrap();
+    fs::create_dir_all(root.path().join("sys/devices/system/cpu")).unwrap();
+
+    assert!(CpuTopology::new(&SysRoot::new(root.path())).is_err());
+
+    write_sysfs_file(root.path(), "sys/devices/system/cpu/online", "garbage\n");
+    assert!(CpuTopology::new(&SysRoot::new(root.path())).is_err());
+}
+
+#[test]
+fn test_missing_optional_files() {
+    let root = tempfile::tempdir().unwrap();
+    let sysroot = SysRoot::new(root.path());
+
+    assert_eq!(read_freq_boost_state(&sysroot), None);
+    assert_eq!(read_cpu_byte_order(&sysroot), None);
+    assert!(read_cpu_vulnerabilities(&sysroot).is_empty());
+}
+
+#[test]
+fn test_topology_counts() {
+    // Two sockets with two dies each, and core IDs that are only unique within a socket
+    let cpu = |index, pkg_id, die_id, core_id| Cpu {
+        index,
+        pkg_id: Some(pkg_id),
+        die_id: Some(die_id),
+        core_id: Some(core_id),
+        ..Default::default()
+    };
+    let topology = CpuTopology {
+        cpus: vec![
+            cpu(0, 0, 0, 0),
+            cpu(1, 0, 1, 1),
+            cpu(2, 1, 0, 0),
+            cpu(3, 1, 1, 1),
+        ],
+    };
+
+    assert_eq!(topology.socket_count(), Some(2));
+    assert_eq!(topology.die_count(), Some(4));
+    assert_eq!(topology.core_count(), Some(4));
+    assert_eq!(topology.cluster_count(), None);
+    assert_eq!(topology.book_count(), None);
+}
+
+#[test]
+fn test_topology_reads_cpus_lists() {
+    let root = tempfile::tempdir().unwrap();
+    write_sysfs_file(root.path(), "sys/devices/system/cpu/online", "0-3\n");
+    for (cpu, core_cpus) in [(0, "0-1"), (1, "0-1"), (2, "2-3"), (3, "2-3")] {
+        let topology = format!("sys/devices/system/cpu/cpu{}/topology", cpu);
+        write_sysfs_file(root.path(), &format!("{}/core_id", topology), "0\n");
+        write_sysfs_file(root.path(), &format!("{}/die_id", topology), "-1\n");
+        write_sysfs_file(
+            root.path(),
+            &format!("{}/core_cpus_list", topology),
+            &format!("{}\n", core_cpus),
+        );
+    }
+
+    let topology = CpuTopology::new(&SysRoot::new(root.path())).unwrap();
+
+    // Core IDs alone would make this look like a single core
+    assert_eq!(topology.core_count(), Some(2));
+    assert_eq!(topology.cpus[0].die_id, None);
+    assert_eq!(topology.die_count(), None);
+}
+
+#[test]
+fn test_snapshot_records_reads() {
+    let root = tempfile::tempdir().unwrap();
+    write_sysfs_file(root.path(), "sys/devices/system/cpu/online", "0\n");
+    write_sysfs_file(root.path(), "proc/1/environ", "container=lxc\0");
+
+    let sysroot = SysRoot::new(root.path()).record();
+    assert!(!sysroot.is_live());
+    assert_eq!(read_online_cpus(&sysroot).unwrap(), "0");
+    assert!(sysroot.read_private("/proc/1/environ").is_ok());
+    assert!(sysroot.read_to_string("/proc/cpuinfo").is_err());
+
+    let snapshot = sysroot.take_snapshot();
+    assert_eq!(
+        snapshot.keys().collect::<Vec<_>>(),
+        [Path::new("sys/devices/system/cpu/online")]
+    );
+    assert_eq!(
+        snapshot[Path::new("sys/devices/system/cpu/online")],
+        Some(b"0\n".to_vec())
+    );
+}
+
+#[test]
+fn test_parse_vulnerability() {
+    let vuln = CpuVulnerability::parse(
+        "spectre_v2",
+        "Mitigation: Enhanced IBRS; IBPB conditional\n",
+    );
+    assert_eq!(vuln.name, "Spectre v2");
+    assert_eq!(vuln.status, VulnerabilityStatus::Mitigation);
+    assert_eq!(vuln.details.unwrap(), "Enhanced IBRS; IBPB conditional");
+    assert_eq!(
+        vuln.description,
+        "Mitigation; Enhanced IBRS; IBPB conditional"
+    );
+
+    let vuln = CpuVulnerability::parse("meltdown", "Not affected\n");
+    assert_eq!(vuln.status, VulnerabilityStatus::NotAffected);
+    assert_eq!(vuln.details, None);
+    assert_eq!(vuln.description, "Not affected");
+
+    assert_eq!(
+        CpuVulnerability::parse("élan_bug", "Not affected\n").name,
+        "Élan bug"
+    );
+    assert_eq!(CpuVulnerability::parse("", "Not affected\n").name, "");
+
+    assert_eq!(
+        parse_vulnerability_status("Vulnerable: No microcode"),
+        (VulnerabilityStatus::Vulnerable, Some("No microcode".into()))
+    );
+    assert_eq!(
+        parse_vulnerability_status("Vulnerable"),
+        (VulnerabilityStatus::Vulnerable, None)
+    );
+    assert_eq!(
+        parse_vulnerability_status("KVM: Mitigation: VMX disabled"),
+        (VulnerabilityStatus::Mitigation, Some("VMX disabled".into()))
+    );
+    assert_eq!(
+        parse_vulnerability_status("Processor vulnerable"),
+        (VulnerabilityStatus::Vulnerable, None)
+    );
+    assert_eq!(
+        parse_vulnerability_status("Unknown: No mitigations"),
+        (VulnerabilityStatus::Unknown, Some("No mitigations".into()))
+    );
+    assert_eq!(
+        parse_vulnerability_status("Something new"),
+        (VulnerabilityStatus::Unknown, Some("Something new".into()))
     );
 }
@@ -0,0 +1,254 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use std::fmt;
+
+use crate::sysfs::SysRoot;
+
+const DMI_FILES: [&str; 4] = [
+    "/sys/class/dmi/id/sys_vendor",
+    "/sys/class/dmi/id/product_name",
+    "/sys/class/dmi/id/bios_vendor",
+    "/sys/class/dmi/id/board_vendor",
+];
+
+#[derive(Debug, Default)]
+pub struct Virtualization {
+    // Hardware virtualization extensions offered by the CPU itself, ie. VT-x or AMD-V
+    pub extensions: Option<&'static str>,
+    pub vendor: Option<String>,
+    pub typ: Option<VirtualizationType>,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VirtualizationType {
+    None,
+    Para,
+    Full,
+    Container,
+}
+
+impl fmt::Display for VirtualizationType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            VirtualizationType::None => write!(f, "none"),
+            VirtualizationType::Para => write!(f, "para"),
+            VirtualizationType::Full => write!(f, "full"),
+            VirtualizationType::Container => write!(f, "container"),
+        }
+    }
+}
+
+impl Virtualization {
+    pub fn is_empty(&self) -> bool {
+        self.extensions.is_none() && self.vendor.is_none() && self.typ.is_none()
+    }
+}
+
+// Detection roughly follows the order util-linux uses: Xen-specific interfaces first, then DMI strings and cpuinfo
+// flags for fully virtualized guests, and container markers only if no hypervisor could be found
+pub fn detect(sysroot: &SysRoot, cpu_flags: &str) -> Virtualization {
+    let flags: Vec<_> = cpu_flags.split_whitespace().collect();
+
+    let mut virt = Virtualization {
+        extensions: virtualization_extensions(&flags),
+        ..Default::default()
+    };
+
+    if let Some((vendor, typ)) = detect_xen(sysroot, &flags) {
+        virt.vendor = Some(vendor.to_string());
+        virt.typ = Some(typ);
+    } else if let Some(vendor) = detect_dmi_vendor(sysroot) {
+        virt.vendor = Some(vendor.to_string());
+        virt.typ = Some(VirtualizationType::Full);
+    } else if flags.contains(&"hypervisor") {
+        virt.typ = Some(VirtualizationType::Full);
+    } else if let Some(container) = detect_container(sysroot) {
+        virt.vendor = Some(container);
+        virt.typ = Some(VirtualizationType::Container);
+    }
+
+    virt
+}
+
+fn virtualization_extensions(flags: &[&str]) -> Option<&'static str> {
+    if flags.contains(&"vmx") {
+        Some("VT-x")
+    } else if flags.contains(&"svm") {
+        Some("AMD-V")
+    } else {
+        None
+    }
+}
+
+fn detect_xen(sysroot: &SysRoot, flags: &[&str]) -> Option<(&'static str, VirtualizationType)> {
+    let is_xen = sysroot.exists("/proc/xen")
+        || matches!(sysroot.read_to_string("/sys/hypervisor/type"), Ok(typ) if typ.trim() == "xen");
+    if !is_xen {
+        return None;
+    }
+
+    // The control domain (dom0) runs on the hypervisor too, but isn't considered virtualized
+    let is_dom0 = sysroot
+        .read_to_string("/proc/xen/capabilities")
+        .map(|caps| caps.contains("control_d"))
+        .unwrap_or(false);
+    if is_dom0 {
+        return Some(("Xen", VirtualizationType::None));
+    }
+
+    let features = sysroot
+        .read_to_string("/sys/hypervisor/properties/features")
+        .ok()
+        .and_then(|features| u32::from_str_radix(features.trim(), 16).ok());
+    Some((
+        "Xen",
+        xen_guest_type(features, flags.contains(&"hypervisor")),
+    ))
+}
+
+// HVM guests have both `/proc/xen` and `/sys/hypervisor/type` too, so PV guests are told apart by the
+// features the hypervisor gives them, like in util-linux. Older kernels without the features file
+// only expose the `hypervisor` cpuinfo flag to HVM guests.
+fn xen_guest_type(features: Option<u32>, has_hypervisor_flag: bool) -> VirtualizationType {
+    const XENFEAT_WRITABLE_PAGE_TABLES: u32 = 1 << 0;
+    const XENFEAT_SUPERVISOR_MODE_KERNEL: u32 = 1 << 3;
+    const XENFEAT_MMU_PT_UPDATE_PRESERVE_AD: u32 = 1 << 5;
+    const XENFEAT_HVM_CALLBACK_VECTOR: u32 = 1 << 8;
+    const PVH_MASK: u32 =
+        XENFEAT_WRITABLE_PAGE_TABLES | XENFEAT_SUPERVISOR_MODE_KERNEL | XENFEAT_HVM_CALLBACK_VECTOR;
+
+    match features {
+        Some(features) if features & XENFEAT_MMU_PT_UPDATE_PRESERVE_AD != 0 => {
+            VirtualizationType::Para
+        }
+        Some(features) if features & PVH_MASK == PVH_MASK => VirtualizationType::Para,
+        Some(_) => VirtualizationType::Full,
+        None if has_hypervisor_flag => VirtualizationType::Full,
+        None => VirtualizationType::Para,
+    }
+}
+
+fn detect_dmi_vendor(sysroot: &SysRoot) -> Option<&'static str> {
+    DMI_FILES
+        .iter()
+        .filter_map(|path| sysroot.read_to_string(path).ok())
+        .find_map(|value| hypervisor_from_dmi(value.trim()))
+}
+
+fn hypervisor_from_dmi(value: &str) -> Option<&'static str> {
+    const DMI_VENDORS: [(&str, &str); 8] = [
+        ("Xen", "Xen"),
+        ("QEMU", "KVM"),
+        ("KVM", "KVM"),
+        ("VMware", "VMware"),
+        ("innotek GmbH", "Innotek GmbH"),
+        ("VirtualBox", "Oracle"),
+        ("Parallels", "Parallels"),
+        ("Virtual Machine", "Microsoft"),
+    ];
+
+    DMI_VENDORS
+        .iter()
+        .find(|(prefix, _)| value.starts_with(prefix))
+        .map(|(_, vendor)| *vendor)
+}
+
+fn detect_container(sysroot: &SysRoot) -> Option<String> {
+    if let Ok(environ) = sysroot.read_private("/proc/1/environ") {
+        let container = container_from_environ(&environ);
+        // The rest of the environment may hold secrets, so a snapshot only keeps the variable that matters
+        let kept = container
+            .as_ref()
+            .map(|name| format!("container={name}\0"))
+            .unwrap_or_default();
+        sysroot.add_to_snapshot("/proc/1/environ", Some(kept.as_bytes()));
+        if container.is_some() {
+            return container;
+        }
+    }
+
+    if sysroot.exists("/.dockerenv") {
+        return Some("docker".to_string());
+    }
+
+    if sysroot.exists("/run/.containerenv") {
+        return Some("podman".to_string());
+    }
+
+    sysroot
+        .read_to_string("/proc/1/cgroup")
+        .ok()
+        .and_then(|cgroup| container_from_cgroup(&cgroup))
+        .map(|container| container.to_string())
+}
+
+// The environment of PID 1 is a NUL-separated list, container managers set `container=<name>` in it
+fn container_from_environ(environ: &[u8]) -> Option<String> {
+    environ
+        .split(|b| *b == b'\0')
+        .filter_map(|var| var.strip_prefix(b"container="))
+        .map(|name| String::from_utf8_lossy(name).to_string())
+        .find(|name| !name.is_empty())
+}
+
+fn container_from_cgroup(cgroup: &str) -> Option<&'static str> {
+    const CGROUP_MARKERS: [(&str, &str); 4] = [
+        ("/kubepods", "kubernetes"),
+        ("/docker", "docker"),
+        ("/libpod", "podman"),
+        ("/lxc", "lxc"),
+    ];
+
+    CGROUP_MARKERS
+        .iter()
+        .find(|(marker, _)| cgroup.contains(marker))
+        .map(|(_, container)| *container)
+}
+
+#[test]
+fn test_virtualization_extensions() {
+    assert_eq!(
+        virtualization_extensions(&["fpu", "vmx", "lm"]),
+        Some("VT-x")
+    );
+    assert_eq!(virtualization_extensions(&["fpu", "svm"]), Some("AMD-V"));
+    assert_eq!(virtualization_extensions(&["fpu", "hypervisor"]), None);
+}
+
+#[test]
+fn test_xen_guest_type() {
+    assert_eq!(xen_guest_type(Some(0x20), true), VirtualizationType::Para);
+    assert_eq!(xen_guest_type(Some(0x109), true), VirtualizationType::Para);
+    assert_eq!(xen_guest_type(Some(0x108), true), VirtualizationType::Full);
+    assert_eq!(xen_guest_type(Some(0x8d1), true), VirtualizationType::Full);
+    assert_eq!(xen_guest_type(None, true), VirtualizationType::Full);
+    assert_eq!(xen_guest_type(None, false), VirtualizationType::Para);
+}
+
+#[test]
+fn test_hypervisor_from_dmi() {
+    assert_eq!(hypervisor_from_dmi("QEMU"), Some("KVM"));
+    assert_eq!(hypervisor_from_dmi("VMware, Inc."), Some("VMware"));
+    assert_eq!(hypervisor_from_dmi("VirtualBox"), Some("Oracle"));
+    assert_eq!(hypervisor_from_dmi("Xen HVM domU"), Some("Xen"));
+    assert_eq!(hypervisor_from_dmi("Dell Inc."), None);
+}
+
+#[test]
+fn test_container_detection() {
+    assert_eq!(
+        container_from_environ(b"HOME=/\0container=podman\0TERM=xterm\0"),
+        Some("podman".to_string())
+    );
+    assert_eq!(container_from_environ(b"HOME=/\0TERM=xterm\0"), None);
+
+    assert_eq!(
+        container_from_cgroup("0::/kubepods/besteffort/pod1234"),
+        Some("kubernetes")
+    );
+    assert_eq!(container_from_cgroup("0::/docker/0123abcd"), Some("docker"));
+    assert_eq!(container_from_cgroup("0::/init.scope"), None);
+}
@@ -5,9 +5,15 @@
 
 use std::{fmt, fs, str::FromStr};
 
-use clap::{crate_version, Command};
+use clap::{crate_version, Arg, ArgAction, Command};
 use uucore::{error::UResult, format_usage, help_about, help_usage};
 
+mod options {
+    pub const JSON: &str = "json";
+    pub const NOHEADINGS: &str = "noheadings";
+    pub const RAW: &str = "raw";
+}
+
 // See https://www.man7.org/linux/man-pages/man5/proc_locks.5.html for details on each field's meaning
 #[derive(Debug)]
 struct Lock {
@@ -23,13 +29,11 @@ struct Lock {
 }
 
 impl Lock {
-    fn get_value(&self, col: &Column) -> String {
-        match col {
+    fn get_value(&self, col: &Column) -> Option<String> {
+        let value = match col {
             Column::Command => resolve_command(self).unwrap_or("<unknown>".to_string()),
-            Column::Pid => self
-                .pid
-                .map(|pid| pid.to_string())
-                .unwrap_or("-".to_string()),
+            // OFD locks belong to an open file description rather than to a process
+            Column::Pid => return self.pid.map(|pid| pid.to_string()),
             Column::Type => self.lock_type.to_string(),
             Column::Size => todo!(),
             Column::Inode => self.inode.to_string(),
@@ -51,7 +55,8 @@ impl Lock {
             Column::Path => todo!(), // TODO: Resolve filepath of the lock target
             Column::Blocker => todo!(), // TODO: Check if lock is blocker (and by what)
             Column::Holders => todo!(), // TODO: Resolve all holders of the lock (this would also let us display a process/PID for OFD locks)
-        }
+        };
+        Some(value)
     }
 }
 
@@ -212,6 +217,23 @@ impl Column {
             Self::Holders => "HOLDERS",
         }
     }
+
+    fn is_right_aligned(&self) -> bool {
+        matches!(
+            self,
+            Self::Pid | Self::Size | Self::Inode | Self::Start | Self::End
+        )
+    }
+
+    fn json_type(&self) -> smartcols::JsonType {
+        match self {
+            Self::Pid | Self::Size | Self::Inode | Self::Start | Self::End => {
+                smartcols::JsonType::Number
+            }
+            Self::Mandatory => smartcols::JsonType::Boolean,
+            _ => smartcols::JsonType::String,
+        }
+    }
 }
 
 fn resolve_command(lock: &Lock) -> Option<String> {
@@ -241,45 +263,48 @@ const DEFAULT_COLS: &[Column] = &[
 
 struct OutputOptions {
     cols: Vec<Column>,
+    format: smartcols::OutputFormat,
+    noheadings: bool,
 }
 
 fn print_output(locks: Vec<Lock>, output_opts: OutputOptions) {
-    let mut column_widths: Vec<_> = output_opts
-        .cols
-        .iter()
-        .map(|col| col.header_text().len())
-        .collect();
-
-    for lock in &locks {
-        for (i, col) in output_opts.cols.iter().enumerate() {
-            column_widths[i] = column_widths[i].max(lock.get_value(col).len());
-        }
+    let mut table = smartcols::Table::new("locks");
+    table.set_format(output_opts.format);
+    table.set_noheadings(output_opts.noheadings);
+
+    for col in &output_opts.cols {
+        table.add_column(
+            smartcols::Column::new(col.header_text())
+                .right(col.is_right_aligned())
+                .json_type(col.json_type())
+                // Cells without a value, such as the PID of an OFD lock, are shown as `-`
+                .placeholder("-"),
+        );
     }
-
-    let headers: Vec<_> = output_opts
-        .cols
-        .iter()
-        .enumerate()
-        .map(|(i, col)| format!("{:<width$}", col.header_text(), width = column_widths[i]))
-        .collect();
-
-    println!("{}", headers.join(" "));
-
     for lock in &locks {
-        let values: Vec<_> = output_opts
-            .cols
-            .iter()
-            .enumerate()
-            .map(|(i, col)| format!("{:<width$}", lock.get_value(col), width = column_widths[i]))
-            .collect();
-        println!("{}", values.join(" "));
+        table.add_line(smartcols::Line::new(
+            output_opts.cols.iter().map(|col| lock.get_value(col)),
+        ));
     }
+
+    table.print();
 }
 
 #[uucore::main]
-pub fn uumain(_args: impl uucore::Args) -> UResult<()> {
+pub fn uumain(args: impl uucore::Args) -> UResult<()> {
+    let matches = uu_app().try_get_matches_from(args)?;
+
+    let format = if matches.get_flag(options::JSON) {
+        smartcols::OutputFormat::Json
+    } else if matches.get_flag(options::RAW) {
+        smartcols::OutputFormat::Raw
+    } else {
+        smartcols::OutputFormat::Table
+    };
     let output_opts = OutputOptions {
         cols: Vec::from(DEFAULT_COLS),
+        format,
+        noheadings: matches.get_flag(options::NOHEADINGS),
     };
 
     let locks: Vec<_> = match fs::read_to_string("/proc/locks") {
@@ -303,4 +328,26 @@ pub fn uu_app() -> Command {
         .about(ABOUT)
         .override_usage(format_usage(USAGE))
         .infer_long_args(true)
+        .arg(
+            Arg::new(options::JSON)
+                .short('J')
+                .long("json")
+                .help("use JSON output format")
+                .action(ArgAction::SetTrue)
+                .conflicts_with(options::RAW),
+        )
+        .arg(
+            Arg::new(options::NOHEADINGS)
+                .short('n')
+                .long("noheadings")
+                .help("don't print headings")
+                .action(ArgAction::SetTrue),
+        )
+        .arg(
+            Arg::new(options::RAW)
+                .short('r')
+                .long("raw")
+                .help("use the raw output format")
+                .action(ArgAction::SetTrue),
+        )
 }
@@ -0,0 +1,364 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+//! Model of the memory blocks exposed in `/sys/devices/system/memory`, shared by lsmem and chmem.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use uucore::error::{FromIo, UResult, USimpleError};
+
+pub const PATH_NAME_MEMORY: &str = "memory";
+pub const PATH_NAME_NODE: &str = "node";
+pub const PATH_NAME_MEMORY_TIER: &str = "memory_tier";
+pub const PATH_SUB_BLOCK_SIZE_BYTES: &str = "block_size_bytes";
+pub const PATH_SUB_NODELIST: &str = "nodelist";
+pub const PATH_SUB_ONLINE_TYPE: &str = "online_type";
+pub const PATH_SUB_PHYS_DEVICE: &str = "phys_device";
+pub const PATH_SUB_REMOVABLE: &str = "removable";
+pub const PATH_SUB_STATE: &str = "state";
+pub const PATH_SUB_VALID_ZONES: &str = "valid_zones";
+pub const PATH_SYS_MEMORY: &str = "/sys/devices/system/memory";
+pub const PATH_SYS_MEMORY_TIERING: &str = "/sys/devices/virtual/memory_tiering";
+pub const PATH_SYS_NODE: &str = "/sys/devices/system/node";
+
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, PartialOrd, Ord, Clone, Copy)]
+pub enum ZoneId {
+    #[serde(rename = "DMA")]
+    ZoneDma,
+    #[serde(rename = "DMA32")]
+    ZoneDma32,
+    #[serde(rename = "Normal")]
+    ZoneNormal,
+    #[serde(rename = "Highmem")]
+    ZoneHighmem,
+    #[serde(rename = "Movable")]
+    ZoneMovable,
+    #[serde(rename = "Device")]
+    ZoneDevice,
+    #[serde(rename = "None")]
+    ZoneNone,
+    #[serde(rename = "Unknown")]
+    ZoneUnknown,
+    #[serde(rename = "MAX_NR_ZONES")]
+    MaxNrZones,
+}
+
+impl core::fmt::Display for ZoneId {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let value = serde_json::to_string(self).unwrap().replace("\"", "");
+        write!(f, "{}", value)
+    }
+}
+
+impl FromStr for ZoneId {
+    type Err = ();
+
+    fn from_str(input: &str) -> Result<ZoneId, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "dma" => Ok(ZoneId::ZoneDma),
+            "dma32" => Ok(ZoneId::ZoneDma32),
+            "normal" => Ok(ZoneId::ZoneNormal),
+            "highmem" => Ok(ZoneId::ZoneHighmem),
+            "movable" => Ok(ZoneId::ZoneMovable),
+            "device" => Ok(ZoneId::ZoneDevice),
+            "none" => Ok(ZoneId::ZoneNone),
+            "unknown" => Ok(ZoneId::ZoneUnknown),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(PartialEq, Clone, Debug)]
+pub enum MemoryState {
+    Online,
+    Offline,
+    GoingOffline,
+    Unknown,
+}
+
+impl core::fmt::Display for MemoryState {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        match self {
+            MemoryState::Online => write!(f, "online"),
+            MemoryState::Offline => write!(f, "offline"),
+            MemoryState::GoingOffline => write!(f, "going-offline"),
+            MemoryState::Unknown => write!(f, "unknown"),
+        }
+    }
+}
+
+impl FromStr for MemoryState {
+    type Err = ();
+    fn from_str(input: &str) -> Result<MemoryState, Self::Err> {
+        match input {
+            "online" => Ok(MemoryState::Online),
+            "offline" => Ok(MemoryState::Offline),
+            "going-offline" => Ok(MemoryState::GoingOffline),
+            "unknown" => Ok(MemoryState::Unknown),
+            _ => Err(()),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct MemoryBlock {
+    pub index: u64,
+    pub count: u64,
+    pub state: MemoryState,
+    pub node: i32,
+    pub nr_zones: usize,
+    pub zones: [ZoneId; ZoneId::MaxNrZones as usize],
+    pub removable: bool,
+    /// Memory tier of the block's node, lower tiers being faster (ie. DRAM before CXL memory)
+    pub tier: Option<u32>,
+    pub phys_device: Option<u64>,
+    /// How the block was onlined: `online_kernel` or `online_movable`
+    pub online_type: Option<String>,
+}
+
+impl MemoryBlock {
+    pub fn new() -> Self {
+        MemoryBlock {
+            index: 0,
+            count: 0,
+            state: MemoryState::Unknown,
+            node: 0,
+            nr_zones: 0,
+            zones: [ZoneId::ZoneUnknown; ZoneId::MaxNrZones as usize],
+            removable: true,
+            tier: None,
+            phys_device: None,
+            online_type: None,
+        }
+    }
+
+    /// The zones this block can be onlined to, as reported by `valid_zones`.
+    pub fn valid_zones(&self) -> &[ZoneId] {
+        &self.zones[..self.nr_zones]
+    }
+}
+
+impl Default for MemoryBlock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Reads the size of a single memory block, which the kernel reports in hexadecimal.
+pub fn read_block_size(sysmem: &str) -> UResult<u64> {
+    let path = Path::new(sysmem).join(PATH_SUB_BLOCK_SIZE_BYTES);
+    let content = read_file_content::<String>(&path)
+        .map_err_context(|| format!("cannot read {}", path.display()))?;
+    u64::from_str_radix(&content, 16).map_err(|_| {
+        USimpleError::new(
+            1,
+            format!("invalid block size in {}: '{}'", path.display(), content),
+        )
+    })
+}
+
+/// Parses the index out of a `<prefix>N` directory name.
+fn parse_index<T: FromStr>(path: &Path, prefix: &str) -> Option<T> {
+    path.file_name()?
+        .to_str()?
+        .strip_prefix(prefix)?
+        .parse()
+        .ok()
+}
+
+/// Lists the `memoryN` directories, sorted by block index.
+pub fn get_block_paths(sysmem: &str) -> UResult<Vec<PathBuf>> {
+    let mut paths = Vec::<(u64, PathBuf)>::new();
+    let entries = fs::read_dir(sysmem).map_err_context(|| format!("cannot read {}", sysmem))?;
+    for entry in entries {
+        let path = entry
+            .map_err_context(|| format!("cannot read {}", sysmem))?
+            .path();
+        if let Some(index) = parse_index::<u64>(&path, PATH_NAME_MEMORY) {
+            if path.is_dir() {
+                paths.push((index, path));
+            }
+        }
+    }
+    paths.sort_by_key(|(index, _)| *index);
+    Ok(paths.into_iter().map(|(_, path)| path).collect())
+}
+
+/// Tells whether the blocks are attached to NUMA nodes and report valid zones, as not every kernel does.
+pub fn detect_nodes_and_zones(paths: &[PathBuf]) -> (bool, bool) {
+    let mut have_nodes = false;
+    let mut have_zones = false;
+
+    for path in paths {
+        if matches!(memory_block_get_node(path), Ok(node) if node >= 0) {
+            have_nodes = true;
+        }
+
+        if fs::read(path.join(PATH_SUB_VALID_ZONES)).is_ok() {
+            have_zones = true;
+        }
+
+        if have_nodes && have_zones {
+            break;
+        }
+    }
+
+    (have_nodes, have_zones)
+}
+
+/// Returns the NUMA node of a block from its `nodeN` link, or -1 if it has none.
+pub fn memory_block_get_node(path: &Path) -> UResult<i32> {
+    let entries =
+        fs::read_dir(path).map_err_context(|| format!("cannot read {}", path.display()))?;
+    for entry in entries {
+        let path = entry
+            .map_err_context(|| format!("cannot read {}", path.display()))?
+            .path();
+        if let Some(node) = parse_index(&path, PATH_NAME_NODE) {
+            if path.is_dir() {
+                return Ok(node);
+            }
+        }
+    }
+    Ok(-1)
+}
+
+pub fn memory_block_read_attrs(
+    path: &Path,
+    have_nodes: bool,
+    have_zones: bool,
+) -> UResult<MemoryBlock> {
+    let mut blk = MemoryBlock::new();
+    blk.count = 1;
+    blk.state = MemoryState::Unknown;
+    blk.index = parse_index(path, PATH_NAME_MEMORY)
+        .ok_or_else(|| USimpleError::new(1, format!("invalid memory block: {}", path.display())))?;
+
+    blk.removable = matches!(
+        read_file_content::<i32>(&path.join(PATH_SUB_REMOVABLE)),
+        Ok(1)
+    );
+
+    if let Ok(state_raw) = read_file_content::<String>(&path.join(PATH_SUB_STATE)) {
+        blk.state = MemoryState::from_str(&state_raw).unwrap_or(MemoryState::Unknown);
+    }
+
+    blk.phys_device = read_file_content(&path.join(PATH_SUB_PHYS_DEVICE)).ok();
+
+    if have_nodes {
+        blk.node = memory_block_get_node(path)?;
+    }
+
+    blk.nr_zones = 0;
+    if have_zones {
+        if let Ok(raw_content) = read_file_content::<String>(&path.join(PATH_SUB_VALID_ZONES)) {
+            for (i, zone_tok) in raw_content
+                .split_whitespace()
+                .take(ZoneId::MaxNrZones as usize)
+                .enumerate()
+            {
+                blk.zones[i] = ZoneId::from_str(zone_tok).unwrap_or(ZoneId::ZoneUnknown);
+                blk.nr_zones += 1;
+            }
+        }
+    }
+
+    // Kernels without a per-block `online_type` still tell it apart through the zone of online blocks
+    blk.online_type = read_file_content(&path.join(PATH_SUB_ONLINE_TYPE)).ok();
+    if blk.online_type.is_none() && blk.state == MemoryState::Online && blk.nr_zones == 1 {
+        blk.online_type = Some(match blk.zones[0] {
+            ZoneId::ZoneMovable => "online_movable".to_string(),
+            _ => "online_kernel".to_string(),
+        });
+    }
+    Ok(blk)
+}
+
+/// Maps each NUMA node to its memory tier, from the `memory_tierN/nodelist` files.
+/// Kernels without memory tiering support have no such directory, which yields an empty map.
+pub fn read_memory_tiers(systiers: &str) -> BTreeMap<i32, u32> {
+    let mut tiers = BTreeMap::new();
+    let Ok(entries) = fs::read_dir(systiers) else {
+        return tiers;
+    };
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let Some(tier) = parse_index::<u32>(&path, PATH_NAME_MEMORY_TIER) else {
+            continue;
+        };
+        if let Ok(nodelist) = read_file_content::<String>(&path.join(PATH_SUB_NODELIST)) {
+            for node in parse_list(&nodelist) {
+                tiers.insert(node, tier);
+            }
+        }
+    }
+    tiers
+}
+
+/// Parses a kernel list such as `0-2,4`, ignoring malformed entries.
+fn parse_list(list: &str) -> Vec<i32> {
+    let mut values = Vec::new();
+    for part in list
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+    {
+        let (first, last) = part.split_once('-').unwrap_or((part, part));
+        if let (Ok(first), Ok(last)) = (first.parse::<i32>(), last.parse::<i32>()) {
+            values.extend(first..=last);
+        }
+    }
+    values
+}
+
+/// Reads and parses the first line of a sysfs attribute.
+pub fn read_file_content<T: FromStr>(path: &Path) -> io::Result<T> {
+    let file = fs::File::open(path)?;
+    let mut reader = BufReader::new(file);
+    let mut content = String::new();
+    reader.read_line(&mut content)?;
+    content
+        .trim()
+        .parse()
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Failed to parse content"))
+}
+
+#[test]
+fn test_memory_block_read_attrs_unknown_values() {
+    let dir = tempfile::tempdir().unwrap();
+    let block = dir.path().join("memory7");
+    fs::create_dir_all(block.join("node1")).unwrap();
+    fs::write(block.join(PATH_SUB_STATE), "frobnicated\n").unwrap();
+    fs::write(block.join(PATH_SUB_VALID_ZONES), "Normal Bogus\n").unwrap();
+
+    let blk = memory_block_read_attrs(&block, true, true).unwrap();
+    assert_eq!(blk.index, 7);
+    assert_eq!(blk.node, 1);
+    assert_eq!(blk.state, MemoryState::Unknown);
+    assert!(!blk.removable);
+    assert_eq!(blk.valid_zones(), [ZoneId::ZoneNormal, ZoneId::ZoneUnknown]);
+
+    assert!(memory_block_read_attrs(&dir.path().join("memoryX"), false, false).is_err());
+}
+
+#[test]
+fn test_read_memory_tiers() {
+    let dir = tempfile::tempdir().unwrap();
+    for (tier, nodelist) in [("memory_tier4", "0-1,3\n"), ("memory_tier22", "2\n")] {
+        fs::create_dir_all(dir.path().join(tier)).unwrap();
+        fs::write(dir.path().join(tier).join(PATH_SUB_NODELIST), nodelist).unwrap();
+    }
+    fs::write(dir.path().join("uevent"), "").unwrap();
+
+    let tiers = read_memory_tiers(dir.path().to_str().unwrap());
+    assert_eq!(
+        tiers.into_iter().collect::<Vec<_>>(),
+        [(0, 4), (1, 4), (2, 22), (3, 4)]
+    );
+    assert!(read_memory_tiers("/definitely/not/here").is_empty());
+}
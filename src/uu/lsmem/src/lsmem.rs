@@ -3,18 +3,23 @@
 // For the full copyright and license information, please view the LICENSE
 // file that was distributed with this source code.
 
-mod utils;
+pub mod blocks;
+pub mod utils;
 
+use blocks::{
+    MemoryBlock, MemoryState, ZoneId, PATH_SYS_MEMORY, PATH_SYS_MEMORY_TIERING, PATH_SYS_NODE,
+};
 use clap::builder::{EnumValueParser, PossibleValue, PossibleValuesParser};
 use clap::{crate_version, Command, ValueEnum};
 use clap::{Arg, ArgAction};
 use serde::{Deserialize, Serialize};
 use std::borrow::Borrow;
-use std::fs;
-use std::io::{self, BufRead, BufReader};
+use std::collections::BTreeMap;
 use std::path::{Path, PathBuf, MAIN_SEPARATOR};
-use std::str::FromStr;
-use uucore::{error::UResult, format_usage, help_about, help_usage};
+use uucore::{
+    error::{UResult, USimpleError},
+    format_usage, help_about, help_usage, show_warning,
+};
 
 const ABOUT: &str = help_about!("lsmem.md");
 const USAGE: &str = help_usage!("lsmem.md");
@@ -33,14 +38,6 @@ mod options {
     pub const SYSROOT: &str = "sysroot";
 }
 
-const PATH_NAME_MEMORY: &str = "memory";
-const PATH_NAME_NODE: &str = "node";
-const PATH_SUB_BLOCK_SIZE_BYTES: &str = "block_size_bytes";
-const PATH_SUB_REMOVABLE: &str = "removable";
-const PATH_SUB_STATE: &str = "state";
-const PATH_SUB_VALID_ZONES: &str = "valid_zones";
-const PATH_SYS_MEMORY: &str = "/sys/devices/system/memory";
-
 #[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 enum Column {
     #[serde(rename = "RANGE")]
@@ -57,6 +54,12 @@ enum Column {
     Node,
     #[serde(rename = "ZONES")]
     Zones,
+    #[serde(rename = "TIER")]
+    Tier,
+    #[serde(rename = "PHYS_DEVICE")]
+    PhysDevice,
+    #[serde(rename = "ONLINE_TYPE")]
+    OnlineType,
 }
 
 impl ValueEnum for Column {
@@ -69,6 +72,9 @@ impl ValueEnum for Column {
             Column::Block,
             Column::Node,
             Column::Zones,
+            Column::Tier,
+            Column::PhysDevice,
+            Column::OnlineType,
         ]
     }
 
@@ -91,6 +97,7 @@ const SPLIT_COLUMNS: &[Column] = &[
     Column::Removable,
     Column::Node,
     Column::Zones,
+    Column::Tier,
 ];
 
 impl Column {
@@ -103,6 +110,9 @@ impl Column {
             Column::Block => "BLOCK",
             Column::Node => "NODE",
             Column::Zones => "ZONES",
+            Column::Tier => "TIER",
+            Column::PhysDevice => "PHYS_DEVICE",
+            Column::OnlineType => "ONLINE_TYPE",
         }
     }
 
@@ -118,6 +128,24 @@ impl Column {
         }
     }
 
+    /// How values wider than the column are shown when the table doesn't fit in the terminal.
+    fn get_overflow(&self) -> smartcols::Overflow {
+        match self {
+            Column::Zones => smartcols::Overflow::Wrap,
+            Column::OnlineType => smartcols::Overflow::Truncate,
+            _ => smartcols::Overflow::None,
+        }
+    }
+
+    fn get_json_type(&self, bytes: bool) -> smartcols::JsonType {
+        match self {
+            Column::Size if bytes => smartcols::JsonType::Number,
+            Column::Removable => smartcols::JsonType::Boolean,
+            Column::Node | Column::Tier | Column::PhysDevice => smartcols::JsonType::Number,
+            _ => smartcols::JsonType::String,
+        }
+    }
+
     fn get_help(&self) -> &'static str {
         match self {
             Column::Range => "start and end address of the memory range",
@@ -127,110 +155,9 @@ impl Column {
             Column::Block => "memory block number or blocks range",
             Column::Node => "numa node of memory",
             Column::Zones => "valid zones for the memory range",
-        }
-    }
-}
-
-#[derive(Debug, Serialize, Deserialize, PartialEq, Clone, Copy)]
-enum ZoneId {
-    #[serde(rename = "DMA")]
-    ZoneDma,
-    #[serde(rename = "DMA32")]
-    ZoneDma32,
-    #[serde(rename = "Normal")]
-    ZoneNormal,
-    #[serde(rename = "Highmem")]
-    ZoneHighmem,
-    #[serde(rename = "Movable")]
-    ZoneMovable,
-    #[serde(rename = "Device")]
-    ZoneDevice,
-    #[serde(rename = "None")]
-    ZoneNone,
-    #[serde(rename = "Unknown")]
-    ZoneUnknown,
-    #[serde(rename = "MAX_NR_ZONES")]
-    MaxNrZones,
-}
-
-impl core::fmt::Display for ZoneId {
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        let value = serde_json::to_string(self).unwrap().replace("\"", "");
-        write!(f, "{}", value)
-    }
-}
-
-impl FromStr for ZoneId {
-    type Err = ();
-
-    fn from_str(input: &str) -> Result<ZoneId, Self::Err> {
-        match input.to_lowercase().as_str() {
-            "dma" => Ok(ZoneId::ZoneDma),
-            "dma32" => Ok(ZoneId::ZoneDma32),
-            "normal" => Ok(ZoneId::ZoneNormal),
-            "highmem" => Ok(ZoneId::ZoneHighmem),
-            "movable" => Ok(ZoneId::ZoneMovable),
-            "device" => Ok(ZoneId::ZoneDevice),
-            "none" => Ok(ZoneId::ZoneNone),
-            "unknown" => Ok(ZoneId::ZoneUnknown),
-            _ => Err(()),
-        }
-    }
-}
-
-#[derive(PartialEq, Clone)]
-enum MemoryState {
-    Online,
-    Offline,
-    GoingOffline,
-    Unknown,
-}
-
-impl core::fmt::Display for MemoryState {
-    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
-        match self {
-            MemoryState::Online => write!(f, "online"),
-            MemoryState::Offline => write!(f, "offline"),
-            MemoryState::GoingOffline => write!(f, "going-offline"),
-            MemoryState::Unknown => write!(f, "unknown"),
-        }
-    }
-}
-
-impl FromStr for MemoryState {
-    type Err = ();
-    fn from_str(input: &str) -> Result<MemoryState, Self::Err> {
-        match input {
-            "online" => Ok(MemoryState::Online),
-            "offline" => Ok(MemoryState::Offline),
-            "going-offline" => Ok(MemoryState::GoingOffline),
-            "unknown" => Ok(MemoryState::Unknown),
-            _ => Err(()),
-        }
-    }
-}
-
-#[derive(Clone)]
-struct MemoryBlock {
-    index: u64,
-    count: u64,
-    state: MemoryState,
-    node: i32,
-    nr_zones: usize,
-    zones: [ZoneId; ZoneId::MaxNrZones as usize],
-    removable: bool,
-}
-
-impl MemoryBlock {
-    fn new() -> Self {
-        MemoryBlock {
-            index: 0,
-            count: 0,
-            state: MemoryState::Unknown,
-            node: 0,
-            nr_zones: 0,
-            zones: [ZoneId::ZoneUnknown; ZoneId::MaxNrZones as usize],
-            removable: true,
+            Column::Tier => "memory tier of the numa node",
+            Column::PhysDevice => "physical device of the memory range",
+            Column::OnlineType => "how the memory range was onlined",
         }
     }
 }
@@ -256,17 +183,18 @@ impl ValueEnum for Summary {
     }
 }
 
-#[derive(Default, Serialize)]
+#[derive(Default)]
 struct TableRow {
     range: String,
     size: String,
     state: String,
     removable: String,
     block: String,
-    #[serde(skip_serializing)]
     node: String,
-    #[serde(skip_serializing)]
     zones: String,
+    tier: String,
+    phys_device: String,
+    online_type: String,
 }
 
 impl TableRow {
@@ -279,6 +207,9 @@ impl TableRow {
             Column::Block => self.block.clone(),
             Column::Node => self.node.clone(),
             Column::Zones => self.zones.clone(),
+            Column::Tier => self.tier.clone(),
+            Column::PhysDevice => self.phys_device.clone(),
+            Column::OnlineType => self.online_type.clone(),
         }
     }
 }
@@ -296,8 +227,13 @@ struct Options {
     split_by_removable: bool,
     split_by_state: bool,
     split_by_zones: bool,
+    split_by_tier: bool,
     /// Default to PATH_SYS_MEMORY, but a prefix can be prepended
     sysmem: String,
+    /// Default to PATH_SYS_NODE, with the same prefix as sysmem
+    sysnode: String,
+    /// Default to PATH_SYS_MEMORY_TIERING, with the same prefix as sysmem
+    systiers: String,
 
     // Set by read_info
     have_nodes: bool,
@@ -305,9 +241,27 @@ struct Options {
 
     // Computed from flags above
     want_summary: bool,
+    /// Per-node and per-zone totals, only shown when --summary is given explicitly
+    want_summary_details: bool,
     want_table: bool,
 }
 
+#[derive(Clone, Copy, Default)]
+struct MemoryTotals {
+    online: u64,
+    offline: u64,
+}
+
+impl MemoryTotals {
+    fn add(&mut self, blk: &MemoryBlock, block_size: u64) {
+        if blk.state == MemoryState::Online {
+            self.online += block_size;
+        } else {
+            self.offline += block_size;
+        }
+    }
+}
+
 struct Lsmem {
     ndirs: usize,
     dirs: Vec<PathBuf>,
@@ -316,6 +270,8 @@ struct Lsmem {
     block_size: u64,
     mem_online: u64,
     mem_offline: u64,
+    nodes: BTreeMap<i32, MemoryTotals>,
+    zones: BTreeMap<ZoneId, MemoryTotals>,
 }
 
 impl Lsmem {
@@ -328,6 +284,8 @@ impl Lsmem {
             block_size: 0,
             mem_online: 0,
             mem_offline: 0,
+            nodes: BTreeMap::new(),
+            zones: BTreeMap::new(),
         }
     }
 }
@@ -346,63 +304,56 @@ impl Options {
             split_by_removable: false,
             split_by_state: false,
             split_by_zones: false,
+            split_by_tier: false,
             sysmem: Path::new(PATH_SYS_MEMORY).display().to_string(),
+            sysnode: Path::new(PATH_SYS_NODE).display().to_string(),
+            systiers: Path::new(PATH_SYS_MEMORY_TIERING).display().to_string(),
 
             have_nodes: false,
             have_zones: false,
 
             want_summary: true, // default true
-            want_table: true,   // default true
+            want_summary_details: false,
+            want_table: true, // default true
         }
     }
 }
 
-fn read_info(lsmem: &mut Lsmem, opts: &mut Options) {
-    let path_block_size = Path::new(&opts.sysmem).join(PATH_SUB_BLOCK_SIZE_BYTES);
-    lsmem.block_size = u64::from_str_radix(
-        &read_file_content::<String>(path_block_size.as_path())
-            .expect("Failed to read memory block size"),
-        16,
-    )
-    .unwrap();
-    lsmem.dirs = get_block_paths(opts);
-    lsmem.dirs.sort_by(|a, b| {
-        let filename_a = a.file_name().expect("Failed parsing memory block name");
-        let filename_a = filename_a.to_str().unwrap();
-        let filename_b = b.file_name().expect("Failed parsing memory block name");
-        let filename_b = filename_b.to_str().unwrap();
-        let idx_a: u64 = filename_a[PATH_NAME_MEMORY.len()..]
-            .parse()
-            .expect("Failed to parse memory block index");
-        let idx_b: u64 = filename_b[PATH_NAME_MEMORY.len()..]
-            .parse()
-            .expect("Failed to parse memory block index");
-        idx_a.cmp(&idx_b)
-    });
+fn read_info(lsmem: &mut Lsmem, opts: &mut Options) -> UResult<()> {
+    lsmem.block_size = blocks::read_block_size(&opts.sysmem)?;
+    lsmem.dirs = blocks::get_block_paths(&opts.sysmem)?;
     lsmem.ndirs = lsmem.dirs.len();
-    for path in lsmem.dirs.iter() {
-        if memory_block_get_node(path).is_ok() {
-            opts.have_nodes = true;
-        }
-
-        let mut p = path.clone();
-        p.push(PATH_SUB_VALID_ZONES);
-        if fs::read(&p).is_ok() {
-            opts.have_zones = true;
-        }
-
-        if opts.have_nodes && opts.have_zones {
-            break;
-        }
-    }
+    (opts.have_nodes, opts.have_zones) = blocks::detect_nodes_and_zones(&lsmem.dirs);
+    let tiers = if opts.have_nodes {
+        blocks::read_memory_tiers(&opts.systiers)
+    } else {
+        BTreeMap::new()
+    };
 
     for i in 0..lsmem.ndirs {
-        let blk = memory_block_read_attrs(opts, &lsmem.dirs[i]);
+        let mut blk =
+            blocks::memory_block_read_attrs(&lsmem.dirs[i], opts.have_nodes, opts.have_zones)?;
+        blk.tier = tiers.get(&blk.node).copied();
         if blk.state == MemoryState::Online {
             lsmem.mem_online += lsmem.block_size;
         } else {
             lsmem.mem_offline += lsmem.block_size;
         }
+        if opts.have_nodes {
+            lsmem
+                .nodes
+                .entry(blk.node)
+                .or_default()
+                .add(&blk, lsmem.block_size);
+        }
+        // Offline blocks list every zone they could be onlined to, the first one being the kernel's default
+        if let Some(zone) = blk.valid_zones().first() {
+            lsmem
+                .zones
+                .entry(*zone)
+                .or_default()
+                .add(&blk, lsmem.block_size);
+        }
         if is_mergeable(lsmem, opts, &blk) {
             lsmem.blocks[lsmem.nblocks - 1].count += 1;
             continue;
@@ -410,19 +361,7 @@ fn read_info(lsmem: &mut Lsmem, opts: &mut Options) {
         lsmem.nblocks += 1;
         lsmem.blocks.push(blk.clone());
     }
-}
-
-fn get_block_paths(opts: &mut Options) -> Vec<PathBuf> {
-    let mut paths = Vec::<PathBuf>::new();
-    for entry in fs::read_dir(&opts.sysmem).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let filename = path.file_name().unwrap().to_str().unwrap();
-        if path.is_dir() && filename.starts_with(PATH_NAME_MEMORY) {
-            paths.push(path);
-        }
-    }
-    paths
+    Ok(())
 }
 
 fn is_mergeable(lsmem: &Lsmem, opts: &Options, blk: &MemoryBlock) -> bool {
@@ -446,6 +385,16 @@ fn is_mergeable(lsmem: &Lsmem, opts: &Options, blk: &MemoryBlock) -> bool {
     if opts.split_by_node && opts.have_nodes && (curr_block.node != blk.node) {
         return false;
     }
+    if opts.split_by_tier && curr_block.tier != blk.tier {
+        return false;
+    }
+    // These aren't split columns upstream, but a merged range would show a single value for blocks that differ
+    if opts.columns.contains(&Column::PhysDevice) && curr_block.phys_device != blk.phys_device {
+        return false;
+    }
+    if opts.columns.contains(&Column::OnlineType) && curr_block.online_type != blk.online_type {
+        return false;
+    }
     if opts.split_by_zones && opts.have_zones {
         if curr_block.nr_zones != blk.nr_zones {
             return false;
@@ -459,58 +408,6 @@ fn is_mergeable(lsmem: &Lsmem, opts: &Options, blk: &MemoryBlock) -> bool {
     true
 }
 
-fn memory_block_get_node(path: &PathBuf) -> Result<i32, <i32 as FromStr>::Err> {
-    for entry in fs::read_dir(path).unwrap() {
-        let entry = entry.unwrap();
-        let path = entry.path();
-        let filename = path.file_name().unwrap().to_str().unwrap();
-        if path.is_dir() && filename.starts_with(PATH_NAME_NODE) {
-            return filename[PATH_NAME_NODE.len()..].parse();
-        }
-    }
-    Ok(-1)
-}
-
-fn memory_block_read_attrs(opts: &Options, path: &PathBuf) -> MemoryBlock {
-    let mut blk = MemoryBlock::new();
-    blk.count = 1;
-    blk.state = MemoryState::Unknown;
-    let filename = path.file_name().unwrap().to_str().unwrap();
-    blk.index = filename[PATH_NAME_MEMORY.len()..].parse().unwrap();
-
-    let mut removable_path = path.clone();
-    removable_path.push(PATH_SUB_REMOVABLE);
-    blk.removable = read_file_content::<i32>(&removable_path).is_ok();
-
-    let mut state_path = path.clone();
-    state_path.push(PATH_SUB_STATE);
-    if let Ok(state_raw) = read_file_content::<String>(&state_path) {
-        blk.state = MemoryState::from_str(&state_raw).unwrap();
-    }
-
-    if opts.have_nodes {
-        blk.node = memory_block_get_node(path).unwrap();
-    }
-
-    blk.nr_zones = 0;
-    if opts.have_zones {
-        let vz_path = path.join(PATH_SUB_VALID_ZONES);
-        if let Ok(raw_content) = read_file_content::<String>(Path::new(&vz_path)) {
-            let zone_toks = raw_content.split(' ').collect::<Vec<&str>>();
-            for (i, zone_tok) in zone_toks
-                .iter()
-                .map(|tok| tok.to_lowercase())
-                .enumerate()
-                .take(std::cmp::min(zone_toks.len(), ZoneId::MaxNrZones as usize))
-            {
-                blk.zones[i] = ZoneId::from_str(&zone_tok).unwrap();
-                blk.nr_zones += 1;
-            }
-        }
-    }
-    blk
-}
-
 fn create_table_rows(lsmem: &Lsmem, opts: &Options) -> Vec<TableRow> {
     let mut table_rows = Vec::<TableRow>::new();
 
@@ -569,125 +466,129 @@ fn create_table_rows(lsmem: &Lsmem, opts: &Options) -> Vec<TableRow> {
                 .join("/");
         }
 
+        // Tier
+        row.tier = blk.tier.map(|tier| tier.to_string()).unwrap_or_default();
+
+        // Physical device
+        row.phys_device = blk
+            .phys_device
+            .map(|device| device.to_string())
+            .unwrap_or_default();
+
+        // Online type
+        row.online_type = blk.online_type.clone().unwrap_or_default();
+
         table_rows.push(row);
     }
     table_rows
 }
 
-fn print_table(lsmem: &Lsmem, opts: &Options) {
-    let table_rows = create_table_rows(lsmem, opts);
-    let mut col_widths = vec![0; opts.columns.len()];
-
-    // Initialize column widths based on pre-defined width hints
-    for (i, column) in opts.columns.iter().enumerate() {
-        col_widths[i] = column.get_width_hint();
-    }
-
-    // Calculate minimum column widths based on the actual data
-    for row in &table_rows {
-        for (i, column) in opts.columns.iter().enumerate() {
-            let width = match column {
-                Column::Range => row.range.len(),
-                Column::Size => row.size.len(),
-                Column::State => row.state.len(),
-                Column::Removable => row.removable.len(),
-                Column::Block => row.block.len(),
-                Column::Node => row.node.len(),
-                Column::Zones => row.zones.len(),
-            };
-            col_widths[i] = col_widths[i].max(width);
-        }
+fn create_table(lsmem: &Lsmem, opts: &Options) -> smartcols::Table {
+    let mut table = smartcols::Table::new("memory");
+    table.set_noheadings(opts.noheadings);
+    table.set_format(if opts.json {
+        smartcols::OutputFormat::Json
+    } else if opts.pairs {
+        smartcols::OutputFormat::Pairs
+    } else if opts.raw {
+        smartcols::OutputFormat::Raw
+    } else {
+        smartcols::OutputFormat::Table
+    });
+
+    for column in &opts.columns {
+        table.add_column(
+            smartcols::Column::new(column.get_name())
+                .right(column.get_float_right())
+                .width_hint(column.get_width_hint())
+                .overflow(column.get_overflow())
+                .json_type(column.get_json_type(opts.bytes)),
+        );
     }
 
-    if !opts.noheadings {
-        let mut column_names = vec![];
-        for (i, column) in opts.columns.iter().enumerate() {
-            let formatted = if column.get_float_right() {
-                format!("{:>width$}", column.get_name(), width = col_widths[i])
-            } else {
-                format!("{:<width$}", column.get_name(), width = col_widths[i])
-            };
-            column_names.push(formatted);
-        }
-        println!("{}", column_names.join(" "));
-    }
-
-    for row in table_rows {
-        let mut column_values = vec![];
-        for (i, column) in opts.columns.iter().enumerate() {
-            let formatted = if column.get_float_right() {
-                format!("{:>width$}", row.get_value(column), width = col_widths[i])
-            } else {
-                format!("{:<width$}", row.get_value(column), width = col_widths[i])
-            };
-            column_values.push(formatted);
-        }
-        println!("{}", column_values.join(" "));
+    for row in create_table_rows(lsmem, opts) {
+        let values = opts
+            .columns
+            .iter()
+            .map(|column| row.get_value(column))
+            .collect::<Vec<_>>();
+        table.add_line(values.into());
     }
+    table
 }
 
-fn print_json(lsmem: &Lsmem, opts: &Options) {
-    let table_rows = create_table_rows(lsmem, opts);
-    let mut memory_records = Vec::new();
-
-    for row in table_rows {
-        let mut record = serde_json::Map::new();
-        for column in &opts.columns {
-            record.insert(
-                column.get_name().to_lowercase(),
-                if column == &Column::Size && opts.bytes {
-                    serde_json::Value::Number(row.get_value(column).parse().unwrap())
-                } else {
-                    serde_json::Value::String(row.get_value(column))
-                },
-            );
-        }
-        memory_records.push(serde_json::Value::Object(record));
+/// Parses an `--output` list. A list starting with `+` is appended to the given columns instead of replacing them.
+fn parse_columns(list: &str, columns: &[Column]) -> UResult<Vec<Column>> {
+    let (mut result, list) = match list.strip_prefix('+') {
+        Some(list) => (columns.to_vec(), list),
+        None => (vec![], list),
+    };
+    for name in list.split(',') {
+        let column = Column::from_str(name.trim(), true)
+            .map_err(|_| USimpleError::new(1, format!("unknown column: {}", name)))?;
+        result.push(column);
     }
-
-    let table_json = serde_json::json!({ "memory": memory_records });
-
-    let mut table_json_string = serde_json::to_string_pretty(&table_json)
-        .unwrap()
-        .replace("  ", "   ") // Ident 3 spaces
-        .replace("},\n      {", "},{"); // Remove newlines between '}, {'
-    table_json_string = table_json_string.replace("\"yes\"", "true");
-    table_json_string = table_json_string.replace("\"no\"", "false");
-    println!("{table_json_string}");
+    Ok(result)
 }
 
-fn print_pairs(lsmem: &Lsmem, opts: &Options) {
-    let table_rows = create_table_rows(lsmem, opts);
+fn print_json(lsmem: &Lsmem, opts: &Options) {
+    let mut table_json = if opts.want_table {
+        create_table(lsmem, opts).to_json()
+    } else {
+        serde_json::Value::Object(serde_json::Map::new())
+    };
 
-    for row in table_rows {
-        let mut pairs = Vec::new();
-        for col in &opts.columns {
-            pairs.push(format!("{}=\"{}\"", col.get_name(), row.get_value(col)));
-        }
-        println!("{}", pairs.join(" "));
+    if opts.want_summary {
+        table_json["summary"] = summary_json(lsmem, opts);
     }
+
+    println!("{}", smartcols::json::to_string(&table_json));
 }
 
-fn print_raw(lsmem: &Lsmem, opts: &Options) {
-    let table_rows = create_table_rows(lsmem, opts);
+fn summary_json(lsmem: &Lsmem, opts: &Options) -> serde_json::Value {
+    let size = |bytes: u64| -> serde_json::Value {
+        if opts.bytes {
+            bytes.into()
+        } else {
+            utils::size_to_human_string(bytes).into()
+        }
+    };
 
-    if !opts.noheadings {
-        let column_names = opts
-            .columns
-            .iter()
-            .map(|col| col.get_name().to_string())
-            .collect::<Vec<String>>();
-        println!("{}", column_names.join(" "));
-    }
+    let mut summary = serde_json::json!({
+        "block_size": size(lsmem.block_size),
+        "online": size(lsmem.mem_online),
+        "offline": size(lsmem.mem_offline),
+    });
 
-    for row in table_rows {
-        let column_values = opts
-            .columns
+    if opts.want_summary_details {
+        let nodes = lsmem
+            .nodes
             .iter()
-            .map(|col| row.get_value(col).to_string())
-            .collect::<Vec<String>>();
-        println!("{}", column_values.join(" "));
+            .map(|(node, totals)| {
+                serde_json::json!({
+                    "node": node,
+                    "online": size(totals.online),
+                    "offline": size(totals.offline),
+                    "memtotal": read_node_memtotal(opts, *node).map(size),
+                })
+            })
+            .collect::<Vec<_>>();
+        let zones = lsmem
+            .zones
+            .iter()
+            .map(|(zone, totals)| {
+                serde_json::json!({
+                    "zone": zone.to_string(),
+                    "online": size(totals.online),
+                    "offline": size(totals.offline),
+                })
+            })
+            .collect::<Vec<_>>();
+        summary["nodes"] = nodes.into();
+        summary["zones"] = zones.into();
     }
+
+    summary
 }
 
 fn print_summary(lsmem: &Lsmem, opts: &Options) {
@@ -704,21 +605,93 @@ fn print_summary(lsmem: &Lsmem, opts: &Options) {
         println!("{:<23} {:>5}", "Total online memory:", mem_online_str);
         println!("{:<23} {:>5}", "Total offline memory:", mem_offline_str);
     }
+
+    if opts.want_summary_details {
+        print_summary_details(lsmem, opts);
+    }
 }
 
-fn read_file_content<T: core::str::FromStr>(path: &Path) -> io::Result<T>
-where
-    T::Err: std::fmt::Debug, // Required to unwrap the result of T::from_str
-{
-    let file = fs::File::open(path).expect("Failed to open file");
-    let mut reader = BufReader::new(file);
-    let mut content = String::new();
-    reader.read_line(&mut content).expect("Failed to read line");
-    content
-        .trim()
-        .to_string()
-        .parse()
-        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Failed to parse content"))
+/// Reads `MemTotal` from the node's meminfo, which is the memory the kernel manages on that node.
+fn read_node_memtotal(opts: &Options, node: i32) -> Option<u64> {
+    let path = Path::new(&opts.sysnode)
+        .join(format!("{}{}", blocks::PATH_NAME_NODE, node))
+        .join("meminfo");
+    let content = std::fs::read_to_string(path).ok()?;
+    let line = content.lines().find(|line| line.contains("MemTotal:"))?;
+    let kb: u64 = line.split_whitespace().rev().nth(1)?.parse().ok()?;
+    Some(kb * 1024)
+}
+
+fn print_summary_details(lsmem: &Lsmem, opts: &Options) {
+    let size = |bytes: u64| {
+        if opts.bytes {
+            bytes.to_string()
+        } else {
+            utils::size_to_human_string(bytes)
+        }
+    };
+
+    if !lsmem.nodes.is_empty() {
+        let mut rows = Vec::new();
+        for (node, totals) in &lsmem.nodes {
+            let memtotal = read_node_memtotal(opts, *node);
+            // Part of every node's memory is reserved by the firmware and the kernel, so MemTotal
+            // should never exceed what its online blocks add up to
+            if let Some(memtotal) = memtotal.filter(|memtotal| *memtotal > totals.online) {
+                show_warning!(
+                    "node {}: meminfo reports {} of memory, but only {} is online",
+                    node,
+                    size(memtotal),
+                    size(totals.online)
+                );
+            }
+            rows.push(vec![
+                Some(node.to_string()),
+                Some(size(totals.online)),
+                Some(size(totals.offline)),
+                memtotal.map(size),
+            ]);
+        }
+        println!();
+        print_summary_table(&["NODE", "ONLINE", "OFFLINE", "MEMTOTAL"], rows);
+    }
+
+    if !lsmem.zones.is_empty() {
+        let rows = lsmem
+            .zones
+            .iter()
+            .map(|(zone, totals)| {
+                vec![
+                    Some(zone.to_string()),
+                    Some(size(totals.online)),
+                    Some(size(totals.offline)),
+                ]
+            })
+            .collect::<Vec<_>>();
+        println!();
+        print_summary_table(&["ZONE", "ONLINE", "OFFLINE"], rows);
+    }
+}
+
+/// Prints a table with the first column aligned left and the others aligned right.
+fn print_summary_table(headers: &[&str], rows: Vec<Vec<Option<String>>>) {
+    let mut table = smartcols::Table::new("summary");
+    for (i, header) in headers.iter().enumerate() {
+        table.add_column(smartcols::Column::new(header).right(i > 0).placeholder("-"));
+    }
+    for row in rows {
+        table.add_line(smartcols::Line::new(row));
+    }
+    table.print();
+}
+
+fn with_sysroot(sysroot: &str, path: &str) -> String {
+    format!(
+        "{}{}{}",
+        sysroot.trim_end_matches(MAIN_SEPARATOR),
+        MAIN_SEPARATOR,
+        path.trim_start_matches(MAIN_SEPARATOR)
+    )
 }
 
 #[uucore::main]
@@ -733,20 +706,15 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     opts.json = matches.get_flag(options::JSON);
     opts.pairs = matches.get_flag(options::PAIRS);
     opts.raw = matches.get_flag(options::RAW);
-    opts.columns = matches
-        .get_many::<Column>(options::OUTPUT)
-        .unwrap_or_default()
-        .map(|c| c.to_owned())
-        .collect::<Vec<Column>>();
-
-    // Only respect --output-all if no column list were provided.
-    // --output takes priority over --output-all.
-    if opts.columns.is_empty() {
-        if matches.get_flag(options::OUTPUT_ALL) {
-            opts.columns = Column::value_variants().to_vec();
-        } else {
-            opts.columns = DEFAULT_COLUMNS.to_vec();
-        }
+    opts.columns = if matches.get_flag(options::OUTPUT_ALL) {
+        Column::value_variants().to_vec()
+    } else {
+        DEFAULT_COLUMNS.to_vec()
+    };
+
+    // Like util-linux, --output replaces the columns, even with --output-all, unless it starts with '+'
+    if let Some(list) = matches.get_one::<String>(options::OUTPUT) {
+        opts.columns = parse_columns(list, &opts.columns)?;
     }
 
     let mut split_columns = matches
@@ -770,6 +738,7 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     opts.split_by_removable = split_columns.contains(&Column::Removable.get_name().to_string());
     opts.split_by_state = split_columns.contains(&Column::State.get_name().to_string());
     opts.split_by_zones = split_columns.contains(&Column::Zones.get_name().to_string());
+    opts.split_by_tier = split_columns.contains(&Column::Tier.get_name().to_string());
 
     if opts.json || opts.pairs || opts.raw {
         opts.want_summary = false;
@@ -777,32 +746,30 @@ pub fn uumain(args: impl uucore::Args) -> UResult<()> {
     if let Some(summary) = matches.get_one::<Summary>(options::SUMMARY) {
         match summary {
             Summary::Never => opts.want_summary = false,
-            Summary::Only => opts.want_table = false,
-            Summary::Always => {} // Default (equivalent to if --summary wasn't provided at all)
+            Summary::Only => {
+                opts.want_summary = true;
+                opts.want_table = false;
+            }
+            Summary::Always => opts.want_summary = true,
         }
+        opts.want_summary_details = opts.want_summary;
     }
 
     if let Some(sysroot) = matches.get_one::<String>(options::SYSROOT) {
-        opts.sysmem = format!(
-            "{}{}{}",
-            sysroot.trim_end_matches(MAIN_SEPARATOR),
-            MAIN_SEPARATOR,
-            opts.sysmem.trim_start_matches(MAIN_SEPARATOR)
-        );
+        opts.sysmem = with_sysroot(sysroot, &opts.sysmem);
+        opts.sysnode = with_sysroot(sysroot, &opts.sysnode);
+        opts.systiers = with_sysroot(sysroot, &opts.systiers);
     }
 
-    read_info(&mut lsmem, &mut opts);
+    read_info(&mut lsmem, &mut opts)?;
+
+    if opts.json {
+        print_json(&lsmem, &opts);
+        return Ok(());
+    }
 
     if opts.want_table {
-        if opts.json {
-            print_json(&lsmem, &opts);
-        } else if opts.pairs {
-            print_pairs(&lsmem, &opts);
-        } else if opts.raw {
-            print_raw(&lsmem, &opts);
-        } else {
-            print_table(&lsmem, &opts);
-        }
+        create_table(&lsmem, &opts).print();
     }
 
     // Padding line between table and summary if both are shown
@@ -829,7 +796,7 @@ pub fn uu_app() -> Command {
                 .long("json")
                 .help("use JSON output format")
                 .action(ArgAction::SetTrue)
-                .conflicts_with_all([options::PAIRS, options::RAW, options::SUMMARY]),
+                .conflicts_with_all([options::PAIRS, options::RAW]),
         )
         .arg(
             Arg::new(options::PAIRS)
@@ -866,11 +833,8 @@ pub fn uu_app() -> Command {
                 .short('o')
                 .long("output")
                 .help("output columns")
-                .ignore_case(true)
                 .action(ArgAction::Set)
-                .value_name("list")
-                .value_delimiter(',')
-                .value_parser(EnumValueParser::<Column>::new()),
+                .value_name("list"),
         )
         .arg(
             Arg::new(options::OUTPUT_ALL)
@@ -920,7 +884,7 @@ pub fn uu_app() -> Command {
                 .value_name("when")
                 .value_delimiter(',')
                 .value_parser(EnumValueParser::<Summary>::new())
-                .conflicts_with_all([options::RAW, options::PAIRS, options::JSON])
+                .conflicts_with_all([options::RAW, options::PAIRS])
                 .num_args(0..=1)
                 .default_missing_value("only"),
         )
@@ -933,3 +897,17 @@ pub fn uu_app() -> Command {
                 .join("\n")
         ))
 }
+
+#[test]
+fn test_parse_columns() {
+    assert_eq!(
+        parse_columns("block,Size", DEFAULT_COLUMNS).unwrap(),
+        [Column::Block, Column::Size]
+    );
+    assert_eq!(
+        parse_columns("+zones", &[Column::Range]).unwrap(),
+        [Column::Range, Column::Zones]
+    );
+    assert!(parse_columns("block,foo", DEFAULT_COLUMNS).is_err());
+    assert!(parse_columns("+", DEFAULT_COLUMNS).is_err());
+}
@@ -0,0 +1,204 @@
+// This file is part of the uutils util-linux package.
+//
+// For the full copyright and license information, please view the LICENSE
+// file that was distributed with this source code.
+
+use crate::common::util::{AtPath, TestScenario};
+
+const SYSMEM: &str = "sys/devices/system/memory";
+
+/// Builds up a fake /sys/devices/system/memory with 128M blocks in the test directory.
+///
+/// Blocks 0-3 are online, blocks 4-7 are offline, block 0 is not removable and
+/// blocks 6 and 7 can only be onlined to the Normal zone.
+fn setup_sysmem(at: &AtPath) {
+    at.mkdir_all(SYSMEM);
+    at.write(&format!("{SYSMEM}/block_size_bytes"), "8000000\n");
+
+    for i in 0..8 {
+        let block = format!("{SYSMEM}/memory{i}");
+        at.mkdir_all(&format!("{block}/node0"));
+        let removable = if i == 0 { "0\n" } else { "1\n" };
+        at.write(&format!("{block}/removable"), removable);
+        let state = if i < 4 { "online\n" } else { "offline\n" };
+        at.write(&format!("{block}/state"), state);
+        let zones = match i {
+            0..=3 => "Normal\n",
+            4 | 5 => "Movable Normal\n",
+            _ => "Normal\n",
+        };
+        at.write(&format!("{block}/valid_zones"), zones);
+    }
+}
+
+fn state_of(at: &AtPath, index: usize) -> String {
+    at.read(&format!("{SYSMEM}/memory{index}/state"))
+        .trim()
+        .to_string()
+}
+
+#[test]
+fn test_invalid_arg() {
+    new_ucmd!().arg("--definitely-invalid").fails().code_is(1);
+}
+
+#[test]
+fn test_requires_action() {
+    new_ucmd!().arg("128M").fails().code_is(1);
+    new_ucmd!().args(&["-e", "-d", "128M"]).fails().code_is(1);
+}
+
+#[test]
+fn test_enable_size() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    ts.ucmd()
+        .args(&["-s", ".", "-v", "-e", "256M"])
+        .succeeds()
+        .no_stderr()
+        .stdout_is(concat!(
+            "Memory Block 4 (0x0000000020000000-0x0000000027ffffff) enabled\n",
+            "Memory Block 5 (0x0000000028000000-0x000000002fffffff) enabled\n",
+        ));
+
+    assert_eq!(state_of(&ts.fixtures, 4), "online");
+    assert_eq!(state_of(&ts.fixtures, 5), "online");
+    assert_eq!(state_of(&ts.fixtures, 6), "offline");
+}
+
+#[test]
+fn test_disable_size_skips_unremovable() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    // Only blocks 1-3 can be removed, so asking for 512M only partially succeeds
+    ts.ucmd()
+        .args(&["-s", ".", "-d", "512M"])
+        .fails()
+        .code_is(64)
+        .stderr_contains("Could only disable 384M of memory");
+
+    assert_eq!(state_of(&ts.fixtures, 0), "online");
+    for i in 1..4 {
+        assert_eq!(state_of(&ts.fixtures, i), "offline");
+    }
+}
+
+#[test]
+fn test_disable_address_range() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    ts.ucmd()
+        .args(&["-s", ".", "-d", "0x10000000-0x1fffffff"])
+        .succeeds()
+        .no_output();
+
+    assert_eq!(state_of(&ts.fixtures, 1), "online");
+    assert_eq!(state_of(&ts.fixtures, 2), "offline");
+    assert_eq!(state_of(&ts.fixtures, 3), "offline");
+}
+
+#[test]
+fn test_enable_blocks() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    ts.ucmd()
+        .args(&["-s", ".", "-v", "-e", "-b", "3-4"])
+        .succeeds()
+        .stdout_is(concat!(
+            "Memory Block 3 (0x0000000018000000-0x000000001fffffff) already enabled\n",
+            "Memory Block 4 (0x0000000020000000-0x0000000027ffffff) enabled\n",
+        ));
+
+    assert_eq!(state_of(&ts.fixtures, 4), "online");
+}
+
+#[test]
+fn test_missing_blocks() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "-b", "7-8"])
+        .fails()
+        .code_is(64)
+        .stderr_contains("Memory Block 8 (0x0000000040000000-0x0000000047ffffff) does not exist");
+
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "-b", "20"])
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+fn test_huge_block_range() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    // The blocks past the last one are reported together rather than walked one by one
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "-b", "6-18446744073709551615"])
+        .fails()
+        .code_is(64)
+        .stderr_is("chmem: Memory Blocks 8-18446744073709551615 do not exist\n");
+    assert_eq!(state_of(&ts.fixtures, 7), "online");
+
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "0x0-0xffffffffffffffff"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("must be aligned to the memory block size");
+}
+
+#[test]
+fn test_enable_zone() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    // Blocks 6 and 7 can't go to the Movable zone, so only 4 and 5 are picked
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "-z", "movable", "1G"])
+        .fails()
+        .code_is(64)
+        .stderr_contains("Could only enable 256M of memory");
+
+    assert_eq!(state_of(&ts.fixtures, 4), "online_movable");
+    assert_eq!(state_of(&ts.fixtures, 5), "online_movable");
+    assert_eq!(state_of(&ts.fixtures, 6), "offline");
+
+    // The kernel reports the zone-specific online types back as plain "online"
+    setup_sysmem(&ts.fixtures);
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "--zone", "Normal", "-b", "6"])
+        .succeeds();
+    assert_eq!(state_of(&ts.fixtures, 6), "online_kernel");
+}
+
+#[test]
+fn test_zone_conflicts_with_disable() {
+    new_ucmd!()
+        .args(&["-d", "-z", "Movable", "128M"])
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+fn test_unaligned() {
+    let ts = TestScenario::new(util_name!());
+    setup_sysmem(&ts.fixtures);
+
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "100M"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("must be a multiple of the memory block size (128M)");
+
+    ts.ucmd()
+        .args(&["-s", ".", "-e", "0x1000-0x8000000"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("must be aligned to the memory block size");
+}
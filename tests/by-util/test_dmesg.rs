@@ -187,3 +187,641 @@ fn test_since_until_invalid_time() {
             .stderr_only("dmesg: invalid time value \"definitely-invalid\"\n");
     }
 }
+
+#[test]
+fn test_since_until_boot() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--since=boot+5000s")
+        .arg("--until=boot + 1h 56min 40s")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[ 5000.000000] LOG_EMERG LOG_KERN\n\
+             [ 6000.000000] LOG_EMERG LOG_LOCAL0\n\
+             [ 7000.000000] LOG_EMERG LOG_LOCAL1\n",
+        );
+}
+
+#[test]
+fn test_since_until_now() {
+    // The records of the fixture were logged long ago
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--since=-10min")
+        .succeeds()
+        .no_stderr()
+        .no_stdout();
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--until=10min ago")
+        .succeeds();
+    assert_eq!(result.stdout_str().lines().count(), 160);
+}
+
+#[test]
+fn test_since_until_negative_separate_arg() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--since")
+        .arg("-10min")
+        .succeeds()
+        .no_stderr()
+        .no_stdout();
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--until")
+        .arg("-10min")
+        .succeeds();
+    assert_eq!(result.stdout_str().lines().count(), 160);
+}
+
+#[test]
+fn test_since_until_invalid_relative_time() {
+    for value in ["boot+", "bootx", "-10 parsecs", "+min"] {
+        new_ucmd!()
+            .arg(format!("--since={value}"))
+            .fails()
+            .stderr_only(format!("dmesg: invalid time value \"{value}\"\n"));
+    }
+}
+
+#[test]
+fn test_since_until_seq() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--since-seq=3")
+        .arg("--until-seq=5")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[ 3000.000000] LOG_EMERG LOG_DAEMON\n\
+             [ 4000.000000] LOG_EMERG LOG_FTP\n\
+             [ 5000.000000] LOG_EMERG LOG_KERN\n",
+        );
+}
+
+#[test]
+fn test_since_seq_conflicts_with_syslog() {
+    new_ucmd!()
+        .arg("--syslog")
+        .arg("--since-seq=3")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_follow_fifo() {
+    use std::ffi::CString;
+    use std::io::Write;
+
+    let ts = TestScenario::new(util_name!());
+    let fifo = CString::new(ts.fixtures.plus_as_string("kmsg.fifo")).unwrap();
+    assert_eq!(unsafe { libc::mkfifo(fifo.as_ptr(), 0o600) }, 0);
+
+    let mut child = ts
+        .ucmd()
+        .arg("--kmsg-file")
+        .arg("kmsg.fifo")
+        .arg("--follow")
+        .arg("--time-format=notime")
+        .run_no_wait();
+
+    // Opening the writing end blocks until dmesg opens the FIFO for reading
+    let mut writer = std::fs::OpenOptions::new()
+        .write(true)
+        .open(ts.fixtures.plus("kmsg.fifo"))
+        .unwrap();
+    writer.write_all(b"6,0,0,-;first message\n\0").unwrap();
+    writer.flush().unwrap();
+
+    // Without --follow, dmesg would stop as soon as there is nothing left to read
+    child.delay(200);
+    assert!(child.is_alive());
+
+    writer.write_all(b"3,1,1000,-;second message\n\0").unwrap();
+    drop(writer);
+
+    child
+        .wait()
+        .unwrap()
+        .success()
+        .no_stderr()
+        .stdout_is("first message\nsecond message\n");
+}
+
+#[test]
+fn test_follow_new_skips_existing_records() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--follow-new")
+        .succeeds()
+        .no_stderr()
+        .no_stdout();
+}
+
+#[test]
+fn test_follow_json_conflict() {
+    new_ucmd!().arg("--follow").arg("--json").fails().code_is(1);
+}
+
+#[test]
+fn test_syslog_kmsg_file_conflict() {
+    for option in ["--syslog", "--read-clear"] {
+        new_ucmd!()
+            .arg(option)
+            .arg("--kmsg-file")
+            .arg("kmsg.input")
+            .fails()
+            .code_is(1);
+    }
+}
+
+#[test]
+fn test_clear_read_clear_conflict() {
+    new_ucmd!()
+        .arg("--clear")
+        .arg("--read-clear")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+fn test_invalid_buffer_size() {
+    new_ucmd!()
+        .arg("--buffer-size=definitely-invalid")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+fn test_invalid_console_level() {
+    for level in ["0", "9", "definitely-invalid"] {
+        new_ucmd!()
+            .arg("--console-level")
+            .arg(level)
+            .fails()
+            .code_is(1)
+            .stderr_only(format!("dmesg: unknown level '{level}'\n"));
+    }
+}
+
+#[test]
+fn test_console_off_on_conflict() {
+    new_ucmd!()
+        .arg("--console-off")
+        .arg("--console-on")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+fn test_kmsg_dict_json() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--json")
+        .succeeds()
+        .no_stderr()
+        .stdout_is_templated_fixture("test_kmsg_dict_json.expected", &[("\r\n", "\n")]);
+}
+
+#[test]
+fn test_filter_subsystem() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--subsystem=usb")
+        .arg("--time-format=notime")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "usb 1-1: new high-speed USB device number 2 using xhci_hcd\n\
+            usb 1-2: USB disconnect, device number 3\n",
+        );
+}
+
+#[test]
+fn test_filter_device() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--device=+usb:1-2,+scsi:0:0:0:0")
+        .arg("--time-format=notime")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "sd 0:0:0:0: [sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\n\
+            usb 1-2: USB disconnect, device number 3\n",
+        );
+}
+
+#[test]
+fn test_color_always() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--color=always")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "\x1b[32m[    1.000000]\x1b[0m \x1b[33musb 1-1: \x1b[0mnew high-speed USB device number 2 using xhci_hcd\n\
+            \x1b[32m[    2.000000]\x1b[0m \x1b[33msd 0:0:0:0: \x1b[0m\x1b[31m[sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\x1b[0m\n\
+            \x1b[32m[    3.000000]\x1b[0m \x1b[33musb 1-2: \x1b[0mUSB disconnect, device number 3\n\
+            \x1b[32m[    4.000000]\x1b[0m message without dictionary\n",
+        );
+}
+
+#[test]
+fn test_color_scheme() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.mkdir_all("config/terminal-colors.d");
+    ts.fixtures.write(
+        "config/terminal-colors.d/dmesg.scheme",
+        "# comment\nerr blue\nsubsys 1;35\n",
+    );
+    ts.ucmd()
+        .env("XDG_CONFIG_HOME", ts.fixtures.plus("config"))
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--color=always")
+        .arg("--level=err")
+        .arg("--time-format=notime")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "\x1b[1;35msd 0:0:0:0: \x1b[0m\x1b[34m[sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\x1b[0m\n",
+        );
+}
+
+#[test]
+fn test_color_never() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--color=never")
+        .succeeds()
+        .no_stderr()
+        .stdout_does_not_contain("\x1b[");
+}
+
+#[test]
+fn test_invalid_color() {
+    new_ucmd!()
+        .arg("--color=definitely-invalid")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_color_auto_disabled() {
+    let ts = TestScenario::new(util_name!());
+    ts.fixtures.mkdir_all("config/terminal-colors.d");
+
+    ts.ucmd()
+        .env("XDG_CONFIG_HOME", ts.fixtures.plus("config"))
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--color")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("\x1b[32m[    1.000000]\x1b[0m");
+
+    ts.fixtures
+        .write("config/terminal-colors.d/dmesg.disable", "");
+    ts.ucmd()
+        .env("XDG_CONFIG_HOME", ts.fixtures.plus("config"))
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--color")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_does_not_contain("\x1b[");
+}
+
+#[test]
+fn test_human_not_terminal() {
+    // Neither colors nor a pager when the output isn't a terminal
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.1")
+        .arg("--human")
+        .succeeds()
+        .no_stderr()
+        .stdout_is_templated_fixture("test_kmsg_time_format_reltime.expected", &[("\r\n", "\n")]);
+}
+
+#[test]
+#[cfg(unix)]
+fn test_human_pager() {
+    let ts = TestScenario::new(util_name!());
+    ts.ucmd()
+        .env("PAGER", "sed s/^/paged:/")
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--human")
+        .arg("--color=never")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_contains("paged:[Nov18 19:34] usb 1-1: new high-speed USB device");
+
+    ts.ucmd()
+        .env("PAGER", "sed s/^/paged:/")
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--human")
+        .arg("--nopager")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_does_not_contain("paged:");
+}
+
+#[test]
+fn test_decode() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--decode")
+        .arg("--level=err,info")
+        .arg("--device=+usb:1-1,+scsi:0:0:0:0")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "kern  :info  : [    1.000000] usb 1-1: new high-speed USB device number 2 using xhci_hcd\n\
+            kern  :err   : [    2.000000] sd 0:0:0:0: [sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\n",
+        );
+}
+
+#[test]
+fn test_decode_json() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--decode")
+        .arg("--json")
+        .arg("--facility=authpriv")
+        .arg("--level=emerg")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "{\n   \"dmesg\": [\n      {\n         \
+            \"fac\": \"authpriv\",\n         \
+            \"pri\": \"emerg\",\n         \
+            \"seq\": 1,\n         \
+            \"time\":  1000.000000,\n         \
+            \"msg\": \"LOG_EMERG LOG_AUTHPRIV\"\n      \
+            }\n   ]\n}\n",
+        );
+}
+
+#[test]
+fn test_raw() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--raw")
+        .arg("--level=err")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "<3>[    2.000000] sd 0:0:0:0: [sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\n",
+        );
+}
+
+#[test]
+fn test_raw_conflicts() {
+    for option in ["--decode", "--json"] {
+        new_ucmd!().arg("--raw").arg(option).fails().code_is(1);
+    }
+}
+
+#[test]
+fn test_kernel_userspace() {
+    let ts = TestScenario::new(util_name!());
+    let kernel = ts
+        .ucmd()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--kernel")
+        .succeeds();
+    assert_eq!(kernel.stdout_str().lines().count(), 8);
+    assert!(kernel.stdout_str().lines().all(|l| l.contains("LOG_KERN")));
+
+    let userspace = ts
+        .ucmd()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--userspace")
+        .succeeds();
+    assert_eq!(userspace.stdout_str().lines().count(), 152);
+    assert!(!userspace.stdout_str().contains("LOG_KERN"));
+}
+
+#[test]
+fn test_grep() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--grep=LOG_ALERT LOG_(AUTH|CRON)$")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[20000.000000] LOG_ALERT LOG_AUTH\n\
+             [22000.000000] LOG_ALERT LOG_CRON\n",
+        );
+}
+
+#[test]
+fn test_grep_ignore_case_exclude() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--grep=log_crit")
+        .arg("--ignore-case")
+        .arg("--exclude=local")
+        .succeeds();
+    assert_eq!(result.stdout_str().lines().count(), 12);
+    assert!(!result.stdout_str().contains("LOG_LOCAL"));
+}
+
+#[test]
+fn test_grep_context() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--grep=LOG_ERR LOG_KERN")
+        .arg("-B2")
+        .arg("-A1")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[63000.000000] LOG_ERR LOG_DAEMON\n\
+             [64000.000000] LOG_ERR LOG_FTP\n\
+             [65000.000000] LOG_ERR LOG_KERN\n\
+             [66000.000000] LOG_ERR LOG_LOCAL0\n",
+        );
+}
+
+#[test]
+fn test_grep_context_after_filters() {
+    // The context records are the ones left by the other filters
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--facility=kern")
+        .arg("--grep=LOG_ERR")
+        .arg("--before-context=1")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[45000.000000] LOG_CRIT LOG_KERN\n\
+             [65000.000000] LOG_ERR LOG_KERN\n",
+        );
+}
+
+#[test]
+fn test_grep_delta() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--grep=LOG_EMERG LOG_(CRON|FTP)$")
+        .arg("--time-format=delta")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "[<    0.000000>] LOG_EMERG LOG_CRON\n\
+             [< 2000.000000>] LOG_EMERG LOG_FTP\n",
+        );
+}
+
+#[test]
+fn test_grep_json() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--grep=LOG_EMERG LOG_(CRON|FTP)$")
+        .arg("--json")
+        .succeeds();
+    assert_eq!(result.stdout_str().matches("\"msg\"").count(), 2);
+    assert!(result
+        .stdout_str()
+        .contains("\"msg\": \"LOG_EMERG LOG_FTP\""));
+}
+
+#[test]
+fn test_grep_invalid_pattern() {
+    new_ucmd!()
+        .arg("--grep=(")
+        .fails()
+        .stderr_only("dmesg: invalid pattern '('\n");
+}
+
+#[test]
+fn test_context_requires_grep() {
+    for option in ["-A1", "-B1"] {
+        new_ucmd!().arg(option).fails().code_is(1);
+    }
+}
+
+#[test]
+fn test_output_format_ndjson() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--output-format=ndjson")
+        .arg("--level=err,info")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "{\"pri\":6,\"seq\":0,\"time\":1.000000,\"msg\":\"usb 1-1: new high-speed USB device number 2 using xhci_hcd\",\"dict\":{\"SUBSYSTEM\":\"usb\",\"DEVICE\":\"+usb:1-1\"}}\n\
+             {\"pri\":3,\"seq\":1,\"time\":2.000000,\"msg\":\"sd 0:0:0:0: [sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\",\"dict\":{\"SUBSYSTEM\":\"scsi\",\"DEVICE\":\"+scsi:0:0:0:0\"}}\n\
+             {\"pri\":6,\"seq\":2,\"time\":3.000000,\"msg\":\"usb 1-2: USB disconnect, device number 3\",\"dict\":{\"SUBSYSTEM\":\"usb\",\"DEVICE\":\"+usb:1-2\"}}\n\
+             {\"pri\":6,\"seq\":3,\"time\":4.000000,\"msg\":\"message without dictionary\"}\n",
+        );
+}
+
+#[test]
+fn test_output_format_ndjson_follow() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--output-format=ndjson")
+        .arg("--follow")
+        .succeeds();
+    let lines: Vec<_> = result.stdout_str().lines().collect();
+    assert_eq!(lines.len(), 160);
+    assert_eq!(
+        lines[159],
+        "{\"pri\":71,\"seq\":159,\"time\":159000.000000,\"msg\":\"LOG_DEBUG LOG_UUCP\"}"
+    );
+}
+
+#[test]
+fn test_output_format_logfmt() {
+    new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input.dict")
+        .arg("--output-format=logfmt")
+        .arg("--decode")
+        .arg("--subsystem=scsi")
+        .succeeds()
+        .no_stderr()
+        .stdout_is(
+            "fac=kern pri=err seq=1 time=2.000000 \
+             msg=\"sd 0:0:0:0: [sda] tag#0 FAILED Result: hostbyte=DID_OK driverbyte=DRIVER_OK\" \
+             SUBSYSTEM=scsi DEVICE=+scsi:0:0:0:0\n",
+        );
+}
+
+#[test]
+fn test_output_format_rfc5424() {
+    let result = new_ucmd!()
+        .arg("--kmsg-file")
+        .arg("kmsg.input")
+        .arg("--output-format=rfc5424")
+        .arg("--grep=LOG_EMERG LOG_(CRON|KERN)$")
+        .succeeds();
+    let lines: Vec<Vec<_>> = result
+        .stdout_str()
+        .lines()
+        .map(|line| line.splitn(8, ' ').collect())
+        .collect();
+    assert_eq!(lines.len(), 2);
+    // The hostname is the third field
+    assert_eq!(lines[0][..2], ["<72>1", "2024-11-18T20:07:32.866807+07:00"]);
+    assert_eq!(lines[0][3..], ["-", "-", "-", "-", "LOG_EMERG LOG_CRON"]);
+    assert_eq!(lines[1][..2], ["<0>1", "2024-11-18T20:57:32.866807+07:00"]);
+    assert_eq!(
+        lines[1][3..],
+        ["kernel", "-", "-", "-", "LOG_EMERG LOG_KERN"]
+    );
+}
+
+#[test]
+fn test_output_format_invalid() {
+    new_ucmd!()
+        .arg("--output-format=xml")
+        .fails()
+        .code_is(1)
+        .stderr_contains("invalid value 'xml'");
+}
+
+#[test]
+fn test_output_format_conflicts() {
+    for option in ["--json", "--raw"] {
+        new_ucmd!()
+            .arg("--output-format=ndjson")
+            .arg(option)
+            .fails()
+            .code_is(1);
+    }
+}
@@ -19,22 +19,39 @@ fn test_hex() {
 #[test]
 #[cfg(target_os = "linux")]
 fn test_json() {
-    let res = new_ucmd!().arg("--json").succeeds();
+    let res = new_ucmd!()
+        .args(&["--json", "--hierarchic=always"])
+        .succeeds();
 
     let stdout = res.no_stderr().stdout_str();
     assert!(stdout.starts_with("{"));
     assert!(stdout.ends_with("}\n"));
 
     res.stdout_contains("\"lscpu\": [")
-        .stdout_contains("\"field\": \"Architecture\"")
-        .stdout_contains("\"field\": \"CPU(s)\"")
+        .stdout_contains("\"field\": \"Architecture:\"")
+        .stdout_contains("\"field\": \"CPU(s):\"")
         .stdout_contains("\"children\": [");
 }
 
+#[test]
+#[cfg(target_os = "linux")]
+fn test_json_flat() {
+    // Output is not a terminal, so `auto` behaves like `never`
+    for args in [&["--json"][..], &["--json", "--hierarchic=never"]] {
+        new_ucmd!()
+            .args(args)
+            .succeeds()
+            .no_stderr()
+            .stdout_contains("\"field\": \"Architecture:\"")
+            .stdout_contains("\"field\": \"CPU op-mode(s):\"")
+            .stdout_does_not_contain("\"children\": [");
+    }
+}
+
 #[test]
 #[cfg(target_os = "linux")]
 fn test_output() {
-    let res = new_ucmd!().succeeds();
+    let res = new_ucmd!().arg("--hierarchic").succeeds();
     let stdout = res.no_stderr().stdout_str();
 
     // Non-exhaustive list of fields we expect
@@ -47,3 +64,484 @@ fn test_output() {
     assert!(stdout.contains("\n  Model name:"));
     assert!(stdout.contains("\n    CPU Family:"));
 }
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn test_architecture() {
+    let res = new_ucmd!().arg("--hierarchic").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    assert!(stdout.contains("Architecture:") && stdout.contains("x86_64\n"));
+    assert!(stdout.contains("\n  CPU op-mode(s):"));
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended() {
+    let res = new_ucmd!().arg("-e").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    let headers: Vec<_> = stdout.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(headers[..3], ["CPU", "SOCKET", "CORE"]);
+    assert!(stdout.lines().count() > 1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended_columns() {
+    let res = new_ucmd!().arg("--extended=cpu,MHZ,scalmhz%").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    let headers: Vec<_> = stdout.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(headers, ["CPU", "MHZ", "SCALMHZ%"]);
+}
+
+#[test]
+fn test_extended_invalid_column() {
+    new_ucmd!()
+        .arg("--extended=definitely-invalid")
+        .fails()
+        .code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended_json() {
+    new_ucmd!()
+        .args(&["--extended=cpu,socket", "--json"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("\"cpus\": [")
+        .stdout_contains("\"cpu\": 0,");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_parse() {
+    let res = new_ucmd!().arg("--parse=CPU,CORE").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    assert!(stdout.starts_with("# The following is the parsable format"));
+    assert!(stdout.contains("\n# CPU,CORE\n"));
+    assert!(stdout.contains("\n0,"));
+}
+
+#[test]
+fn test_parse_default_columns() {
+    // Without a list, the columns and their header are the ones scripts have long relied on
+    new_ucmd!()
+        .args(&["--sysroot", "two-sockets", "-p"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("\n# CPU,Core,Socket,Node,,L1d,L1i,L2,L3\n")
+        .stdout_is_fixture("two-sockets-parse.expected");
+
+    new_ucmd!()
+        .args(&["--sysroot", "two-sockets", "--parse=CPU,NODE,CACHE"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("\n# CPU,NODE,L1d:L1i:L2:L3\n")
+        .stdout_contains("\n2,1,2:2:2:1\n");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_caches_columns() {
+    let res = new_ucmd!().arg("--caches=name,level").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    let headers: Vec<_> = stdout.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(headers, ["NAME", "LEVEL"]);
+}
+
+#[test]
+fn test_extended_conflicts_with_parse() {
+    new_ucmd!().args(&["-e", "-p"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(all(target_os = "linux", target_arch = "x86_64"))]
+fn test_flags() {
+    new_ucmd!()
+        .arg("--flags")
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("ISA level:")
+        .stdout_contains("x86-64-v")
+        .stdout_contains("SIMD:");
+}
+
+#[test]
+fn test_flags_conflicts_with_extended() {
+    new_ucmd!().args(&["--flags", "-e"]).fails().code_is(1);
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_extended_topology_columns() {
+    let res = new_ucmd!()
+        .arg("--extended=cpu,drawer,book,socket,die,cluster,core")
+        .succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    let headers: Vec<_> = stdout.lines().next().unwrap().split_whitespace().collect();
+    assert_eq!(
+        headers,
+        ["CPU", "DRAWER", "BOOK", "SOCKET", "DIE", "CLUSTER", "CORE"]
+    );
+}
+
+#[test]
+fn test_sysroot() {
+    for (args, expected) in [
+        (&[][..], "two-sockets.expected"),
+        (&["-e"], "two-sockets-extended.expected"),
+        (&["-C"], "two-sockets-caches.expected"),
+        (&["--flags"], "two-sockets-flags.expected"),
+    ] {
+        new_ucmd!()
+            .args(&["--sysroot", "two-sockets"])
+            .args(args)
+            .succeeds()
+            .no_stderr()
+            .stdout_is_fixture(expected);
+    }
+}
+
+#[test]
+fn test_hierarchic_auto_without_tty() {
+    // The output of the tests goes to a pipe, where `auto` flattens the subsections like `never`
+    let ts = TestScenario::new(util_name!());
+    for args in [&["--json"][..], &[]] {
+        let never = ts
+            .ucmd()
+            .args(&["--sysroot", "two-sockets", "--hierarchic=never"])
+            .args(args)
+            .succeeds()
+            .stdout_move_str();
+        for hierarchic in [&["--hierarchic=auto"][..], &[]] {
+            ts.ucmd()
+                .args(&["--sysroot", "two-sockets"])
+                .args(hierarchic)
+                .args(args)
+                .succeeds()
+                .stdout_is(&never);
+        }
+    }
+
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets"])
+        .succeeds()
+        .stdout_does_not_contain("\n ")
+        .stdout_contains("\nVulnerability Srbds:");
+}
+
+#[test]
+fn test_sysroot_without_model_name() {
+    // aarch64 has neither `vendor_id` nor `model name` in its cpuinfo
+    new_ucmd!()
+        .args(&["--sysroot", "aarch64", "--hierarchic"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("\nCPU(s) scaling MHz:    50%\n")
+        .stdout_contains("\nCPU max MHz:           1500.0000\n")
+        .stdout_contains("\nCPU min MHz:           600.0000\n")
+        .stdout_is_fixture("aarch64.expected");
+}
+
+#[test]
+fn test_sysroot_books_and_drawers() {
+    // s390x has a `vendor_id` but no `model name` in its cpuinfo
+    new_ucmd!()
+        .args(&["--sysroot", "s390x", "--hierarchic"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("\nBook(s):               2\n")
+        .stdout_contains("\nDrawer(s):             2\n")
+        .stdout_is_fixture("s390x.expected");
+}
+
+#[test]
+fn test_sysroot_missing() {
+    new_ucmd!()
+        .args(&["--sysroot", "definitely-missing"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot read definitely-missing/sys/devices/system/cpu/online");
+}
+
+#[test]
+fn test_dump_and_replay() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "--dump", "snapshot"])
+        .succeeds()
+        .stdout_is_fixture("two-sockets.expected");
+
+    assert!(ts.fixtures.file_exists("snapshot/proc/cpuinfo"));
+    assert!(ts.fixtures.file_exists("snapshot/sys/kernel/cpu_byteorder"));
+
+    ts.ucmd()
+        .args(&["--sysroot", "snapshot"])
+        .succeeds()
+        .stdout_is_fixture("two-sockets.expected");
+}
+
+#[test]
+fn test_dump_replays_every_mode() {
+    let ts = TestScenario::new(util_name!());
+    let modes = [
+        &[][..],
+        &["-e"],
+        &["-p"],
+        &["-C"],
+        &["--flags"],
+        &["--json"],
+    ];
+
+    // Whichever mode the dump is taken with, it holds what all the others read
+    for (i, dump_args) in modes.iter().enumerate() {
+        let snapshot = format!("snapshot{i}");
+        ts.ucmd()
+            .args(&["--sysroot", "two-sockets", "--dump", &snapshot])
+            .args(dump_args)
+            .succeeds();
+
+        for args in modes {
+            let expected = ts
+                .ucmd()
+                .args(&["--sysroot", "two-sockets"])
+                .args(args)
+                .succeeds()
+                .stdout_move_str();
+            ts.ucmd()
+                .args(&["--sysroot", &snapshot])
+                .args(args)
+                .succeeds()
+                .stdout_is(expected);
+        }
+    }
+}
+
+#[test]
+fn test_dump_container() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu");
+    ts.fixtures.write(
+        "virt/proc/1/environ",
+        "HOME=/root\0container=lxc\0TOKEN=secret\0",
+    );
+
+    ts.ucmd()
+        .args(&["--sysroot", "virt", "-e", "--dump", "snapshot"])
+        .succeeds();
+
+    // Only the variable telling the container apart is kept from the environment of PID 1
+    assert_eq!(
+        ts.fixtures.read("snapshot/proc/1/environ"),
+        "container=lxc\0"
+    );
+    ts.ucmd()
+        .args(&["--sysroot", "snapshot"])
+        .succeeds()
+        .stdout_contains("Hypervisor vendor:")
+        .stdout_contains(" lxc\n");
+}
+
+#[test]
+fn test_sysroot_without_architecture() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu");
+
+    // The architecture of the machine running lscpu isn't the one of the system root
+    ts.ucmd()
+        .args(&["--sysroot", "virt", "--dump", "snapshot"])
+        .succeeds()
+        .stdout_contains("Architecture:\n");
+    assert!(!ts.fixtures.file_exists("snapshot/proc/sys/kernel/arch"));
+}
+
+#[test]
+fn test_dump_tarball() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "--dump", "snapshot.tar"])
+        .succeeds();
+
+    let tarball = ts.fixtures.read_bytes("snapshot.tar");
+    assert_eq!(tarball.len() % 512, 0);
+    assert!(tarball.starts_with(b"proc/cpuinfo\0"));
+}
+
+#[test]
+fn test_vulnerabilities_json() {
+    new_ucmd!()
+        .args(&["--sysroot", "two-sockets", "--json"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains(
+            "         \"field\": \"Vulnerability Spectre v2:\",\n         \
+            \"data\": \"Mitigation; Enhanced / Automatic IBRS; IBPB conditional\",\n         \
+            \"status\": \"Mitigation\",\n         \
+            \"details\": \"Enhanced / Automatic IBRS; IBPB conditional\"\n",
+        )
+        .stdout_contains(
+            "         \"status\": \"Not affected\",\n         \
+            \"details\": null\n",
+        );
+}
+
+#[test]
+fn test_exit_on_vulnerable() {
+    let ts = TestScenario::new(util_name!());
+
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "--exit-on-vulnerable"])
+        .fails()
+        .code_is(2)
+        .no_stderr()
+        .stdout_is_fixture("two-sockets.expected");
+
+    // The check doesn't depend on the vulnerabilities being shown
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "-e", "--exit-on-vulnerable"])
+        .fails()
+        .code_is(2)
+        .stdout_is_fixture("two-sockets-extended.expected");
+
+    // Without the unmitigated vulnerability, the check passes
+    ts.fixtures
+        .remove("two-sockets/sys/devices/system/cpu/vulnerabilities/srbds");
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "--exit-on-vulnerable"])
+        .succeeds();
+
+    // A status that can't be told to be mitigated fails the check as well
+    ts.fixtures.write(
+        "two-sockets/sys/devices/system/cpu/vulnerabilities/tsa",
+        "Unknown: Dependent on hypervisor status\n",
+    );
+    ts.ucmd()
+        .args(&["--sysroot", "two-sockets", "--exit-on-vulnerable"])
+        .fails()
+        .code_is(2);
+}
+
+// A minimal system root with a single CPU, to which each test adds the files it's about
+fn virt_sysroot(ts: &TestScenario, cpu_flags: &str) {
+    ts.fixtures.mkdir_all("virt/proc/1");
+    ts.fixtures.mkdir_all("virt/sys/devices/system/cpu");
+    ts.fixtures
+        .write("virt/sys/devices/system/cpu/online", "0\n");
+    ts.fixtures.write(
+        "virt/proc/cpuinfo",
+        &format!("processor\t: 0\nflags\t\t: {cpu_flags}\n"),
+    );
+}
+
+fn virt_summary(ts: &TestScenario) -> Vec<String> {
+    let result = ts.ucmd().args(&["--sysroot", "virt"]).succeeds();
+    result
+        .stdout_str()
+        .lines()
+        .filter(|line| {
+            line.starts_with("Hypervisor vendor:") || line.starts_with("Virtualization type:")
+        })
+        .map(|line| line.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+#[test]
+fn test_virt_xen() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu");
+    ts.fixtures.mkdir_all("virt/proc/xen");
+    ts.fixtures.write("virt/proc/xen/capabilities", "");
+    ts.fixtures.mkdir_all("virt/sys/hypervisor/properties");
+    ts.fixtures.write("virt/sys/hypervisor/type", "xen\n");
+
+    // PV guests get mmu_pt_update_preserve_ad
+    ts.fixtures
+        .write("virt/sys/hypervisor/properties/features", "000000a5\n");
+    assert_eq!(
+        virt_summary(&ts),
+        ["Hypervisor vendor: Xen", "Virtualization type: para"]
+    );
+
+    ts.fixtures
+        .write("virt/sys/hypervisor/properties/features", "000008d1\n");
+    assert_eq!(
+        virt_summary(&ts),
+        ["Hypervisor vendor: Xen", "Virtualization type: full"]
+    );
+
+    ts.fixtures
+        .write("virt/proc/xen/capabilities", "control_d\n");
+    assert_eq!(
+        virt_summary(&ts),
+        ["Hypervisor vendor: Xen", "Virtualization type: none"]
+    );
+}
+
+#[test]
+fn test_virt_xen_hvm_without_features() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu hypervisor");
+    ts.fixtures.mkdir_all("virt/sys/hypervisor");
+    ts.fixtures.write("virt/sys/hypervisor/type", "xen\n");
+    assert_eq!(
+        virt_summary(&ts),
+        ["Hypervisor vendor: Xen", "Virtualization type: full"]
+    );
+}
+
+#[test]
+fn test_virt_dmi() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu hypervisor");
+    ts.fixtures.mkdir_all("virt/sys/class/dmi/id");
+    ts.fixtures
+        .write("virt/sys/class/dmi/id/sys_vendor", "QEMU\n");
+    assert_eq!(
+        virt_summary(&ts),
+        ["Hypervisor vendor: KVM", "Virtualization type: full"]
+    );
+}
+
+#[test]
+fn test_virt_container() {
+    let ts = TestScenario::new(util_name!());
+    virt_sysroot(&ts, "fpu");
+    assert!(virt_summary(&ts).is_empty());
+
+    ts.fixtures
+        .write("virt/proc/1/cgroup", "0::/kubepods/besteffort/pod1234\n");
+    assert_eq!(
+        virt_summary(&ts),
+        [
+            "Hypervisor vendor: kubernetes",
+            "Virtualization type: container"
+        ]
+    );
+
+    ts.fixtures.write("virt/.dockerenv", "");
+    assert_eq!(
+        virt_summary(&ts),
+        [
+            "Hypervisor vendor: docker",
+            "Virtualization type: container"
+        ]
+    );
+
+    ts.fixtures
+        .write("virt/proc/1/environ", "HOME=/\0container=podman\0");
+    assert_eq!(
+        virt_summary(&ts),
+        [
+            "Hypervisor vendor: podman",
+            "Virtualization type: container"
+        ]
+    );
+}
@@ -20,3 +20,28 @@ fn test_column_headers() {
         vec!["COMMAND", "PID", "TYPE", "MODE", "M", "START", "END"]
     );
 }
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_noheadings() {
+    new_ucmd!()
+        .arg("--noheadings")
+        .succeeds()
+        .no_stderr()
+        .stdout_does_not_contain("COMMAND");
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn test_json() {
+    let res = new_ucmd!().arg("--json").succeeds();
+    let stdout = res.no_stderr().stdout_str();
+
+    assert!(stdout.starts_with("{\n   \"locks\": ["));
+    assert!(stdout.ends_with("}\n"));
+}
+
+#[test]
+fn test_json_conflicts_with_raw() {
+    new_ucmd!().args(&["--json", "--raw"]).fails().code_is(1);
+}
@@ -4,7 +4,7 @@
 // file that was distributed with this source code.
 
 use crate::common::util::TestScenario;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 
 fn write_file_content(dir: &Path, name: &str, content: &str) {
     std::fs::create_dir_all(dir).unwrap();
@@ -66,6 +66,38 @@ impl TestSysMemory {
     }
 }
 
+impl TestSysMemory {
+    fn block_dir(&self, index: usize) -> PathBuf {
+        Path::new(&self.sysroot).join(format!("sys/devices/system/memory/memory{index}"))
+    }
+
+    /// Moves the blocks from node 0 to another NUMA node.
+    fn set_node(&self, blocks: impl IntoIterator<Item = usize>, node: usize) {
+        for i in blocks {
+            let block_dir = self.block_dir(i);
+            std::fs::rename(
+                block_dir.join("node0"),
+                block_dir.join(format!("node{node}")),
+            )
+            .unwrap();
+        }
+    }
+
+    fn set_phys_device(&self, blocks: impl IntoIterator<Item = usize>, device: usize) {
+        for i in blocks {
+            write_file_content(&self.block_dir(i), "phys_device", &format!("{device}\n"));
+        }
+    }
+
+    /// Puts the nodes of `nodelist` in a memory tier, as /sys/devices/virtual/memory_tiering does.
+    fn set_tier(&self, tier: u32, nodelist: &str) {
+        let tier_dir = Path::new(&self.sysroot)
+            .join("sys/devices/virtual/memory_tiering")
+            .join(format!("memory_tier{tier}"));
+        write_file_content(&tier_dir, "nodelist", &format!("{nodelist}\n"));
+    }
+}
+
 impl Drop for TestSysMemory {
     fn drop(&mut self) {
         std::fs::remove_dir_all(&self.sysroot).unwrap();
@@ -304,8 +336,37 @@ fn test_summary_only() {
 }
 
 #[test]
-fn test_summary_conflict_json() {
-    new_ucmd!().arg("--summary").arg("-J").fails().code_is(1);
+fn test_summary_json() {
+    let test_root = TestSysMemory::new();
+    sysroot_test_with_args(
+        &test_root,
+        "test_lsmem_summary_json.expected",
+        &["--summary", "-J"],
+    );
+}
+
+#[test]
+fn test_summary_node_meminfo() {
+    let test_root = TestSysMemory::new();
+    let node_dir = Path::new(&test_root.sysroot).join("sys/devices/system/node/node0");
+    write_file_content(&node_dir, "meminfo", "Node 0 MemTotal:       16000000 kB\n");
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .arg("--summary")
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("0     15.6G      0B    15.3G\n");
+
+    // More memory than the online blocks add up to means sysfs is inconsistent
+    write_file_content(&node_dir, "meminfo", "Node 0 MemTotal:       17000000 kB\n");
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .arg("--summary")
+        .succeeds()
+        .stderr_contains("node 0: meminfo reports 16.2G of memory, but only 15.6G is online");
 }
 
 #[test]
@@ -341,3 +402,164 @@ fn test_table_noheadings() {
     let test_root = TestSysMemory::new();
     sysroot_test_with_args(&test_root, "test_lsmem_table_noheadings.expected", &["-n"]);
 }
+
+#[test]
+fn test_missing_block_size() {
+    let test_root = TestSysMemory::new();
+    let sysmem = Path::new(&test_root.sysroot).join("sys/devices/system/memory");
+    std::fs::remove_file(sysmem.join("block_size_bytes")).unwrap();
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot read ")
+        .stderr_contains("block_size_bytes: No such file or directory");
+}
+
+#[test]
+fn test_missing_sysmem() {
+    new_ucmd!()
+        .args(&["-s", "/definitely/not/a/sysroot"])
+        .fails()
+        .code_is(1)
+        .stderr_contains("cannot read /definitely/not/a/sysroot/sys/devices/system/memory");
+}
+
+#[test]
+fn test_unknown_state_and_zone() {
+    let test_root = TestSysMemory::new();
+    let block = Path::new(&test_root.sysroot).join("sys/devices/system/memory/memory149");
+    write_file_content(&block, "state", "frobnicated\n");
+    write_file_content(&block, "valid_zones", "Bogus Normal\n");
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["-o", "block,state,zones", "-r", "-n"])
+        .succeeds()
+        .no_stderr()
+        .stdout_contains("149 ? Normal\n");
+}
+
+/// A DRAM node and a slower CXL node in a higher memory tier, part of which is offline.
+fn tiered_sys_memory() -> TestSysMemory {
+    let test_root = TestSysMemory::new();
+    let cxl_blocks = 140..=149;
+    test_root.set_node(cxl_blocks.clone(), 1);
+    test_root.set_phys_device(MEMORY_BLOCK_IDS.iter().copied().filter(|i| *i < 140), 0);
+    test_root.set_phys_device(cxl_blocks.clone(), 1);
+    for i in cxl_blocks {
+        let state = if i < 146 { "online\n" } else { "offline\n" };
+        write_file_content(&test_root.block_dir(i), "state", state);
+        write_file_content(&test_root.block_dir(i), "valid_zones", "Movable\n");
+    }
+    test_root.set_tier(4, "0");
+    test_root.set_tier(22, "1");
+    test_root
+}
+
+#[test]
+fn test_tier_columns() {
+    let test_root = tiered_sys_memory();
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["-o", "block,node,tier,phys_device,online_type", "-r"])
+        .succeeds()
+        .no_stderr()
+        .stdout_is(concat!(
+            "BLOCK NODE TIER PHYS_DEVICE ONLINE_TYPE\n",
+            "0-6 0 4 0 online_kernel\n",
+            "32-139 0 4 0 online_kernel\n",
+            "140-145 1 22 1 online_movable\n",
+            "146-149 1 22 1 \n",
+        ));
+}
+
+#[test]
+fn test_split_tier() {
+    let test_root = tiered_sys_memory();
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["-o", "block,size", "-S", "tier", "-r", "-n"])
+        .succeeds()
+        .no_stderr()
+        .stdout_is("0-6 896M\n32-139 13.5G\n140-149 1.3G\n");
+}
+
+#[test]
+fn test_output_append() {
+    let test_root = TestSysMemory::new();
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["-o", "+zones,Node", "-r"])
+        .succeeds()
+        .no_stderr()
+        .stdout_is(concat!(
+            "RANGE SIZE STATE REMOVABLE BLOCK ZONES NODE\n",
+            "0x0000000000000000-0x0000000007ffffff 128M online yes 0 None 0\n",
+            "0x0000000008000000-0x0000000037ffffff 768M online yes 1-6 DMA32 0\n",
+            "0x0000000100000000-0x00000004afffffff 14.8G online yes 32-149 Normal 0\n",
+        ));
+}
+
+#[test]
+fn test_output_all_replaced_or_appended() {
+    let test_root = TestSysMemory::new();
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["--output-all", "-o", "BLOCK", "-r", "-n"])
+        .succeeds()
+        .stdout_is("0-6\n32-149\n");
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["--output-all", "-o", "+block", "-r"])
+        .succeeds()
+        .stdout_contains(
+            "RANGE SIZE STATE REMOVABLE BLOCK NODE ZONES TIER PHYS_DEVICE ONLINE_TYPE BLOCK\n",
+        );
+}
+
+#[test]
+fn test_output_unknown_column() {
+    new_ucmd!()
+        .args(&["-o", "block,foo"])
+        .fails()
+        .code_is(1)
+        .stderr_is("lsmem: unknown column: foo\n");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_narrow_terminal_wraps_zones() {
+    let test_root = TestSysMemory::new();
+    let block = Path::new(&test_root.sysroot).join("sys/devices/system/memory/memory149");
+    write_file_content(&block, "state", "offline\n");
+    write_file_content(&block, "valid_zones", "Normal Movable\n");
+
+    new_ucmd!()
+        .arg("-s")
+        .arg(&test_root.sysroot)
+        .args(&["-o", "block,zones", "-S", "zones", "--summary=never"])
+        .env("COLUMNS", "13")
+        .terminal_simulation(true)
+        .succeeds()
+        .stdout_is(concat!(
+            " BLOCK  ZONES\r\n",
+            "     0   None\r\n",
+            "   1-6  DMA32\r\n",
+            "32-148 Normal\r\n",
+            "   149 Normal\r\n",
+            "       Movabl\r\n",
+            "            e\r\n",
+        ));
+}